@@ -0,0 +1,646 @@
+/*
+ ** Copyright (C) 2021 KunoiSayami
+ **
+ ** This program is free software: you can redistribute it and/or modify
+ ** it under the terms of the GNU Affero General Public License as published by
+ ** the Free Software Foundation, either version 3 of the License, or
+ ** any later version.
+ **
+ ** This program is distributed in the hope that it will be useful,
+ ** but WITHOUT ANY WARRANTY; without even the implied warranty of
+ ** MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ ** GNU Affero General Public License for more details.
+ **
+ ** You should have received a copy of the GNU Affero General Public License
+ ** along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::configure::Repository as RepositoryConfig;
+use crate::datastructures::{
+    self, escape_html, Commit, DisplayableEvent, EventKind, PushKind, Response,
+};
+use crate::provider::{
+    check_header, header_str, hmac_sha256_hex, is_push_enabled, NormalizedEvent, WebhookProvider,
+};
+use axum::http::HeaderMap;
+use serde_derive::{Deserialize, Serialize};
+use std::fmt::Formatter;
+
+pub struct BitbucketProvider;
+
+impl BitbucketProvider {
+    fn parse_event(event_type: &str, body: &[u8]) -> Result<BitbucketEvent, Response> {
+        match BitbucketEvent::parse(event_type, body) {
+            Ok(Some(event)) => Ok(event),
+            Ok(None) => Err(Response::reason(
+                400,
+                format!("Unsupported event type {:?}", event_type),
+            )),
+            Err(e) => Err(Response::new_parse_error(e)),
+        }
+    }
+}
+
+impl WebhookProvider for BitbucketProvider {
+    fn event_type(&self, headers: &HeaderMap) -> Option<String> {
+        header_str(headers, "X-Event-Key")
+    }
+
+    /// Empty if event is not supported, it will be rejected by `parse` later.
+    fn full_name(&self, event_type: &str, body: &[u8]) -> serde_json::Result<String> {
+        Ok(BitbucketEvent::parse(event_type, body)?
+            .map(|x| x.get_full_name().to_string())
+            .unwrap_or_default())
+    }
+
+    /// Both Cloud and Server sign with the same scheme as GitHub, but in `X-Hub-Signature`.
+    fn verify_signature(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        secrets: &str,
+    ) -> Result<(), &'static str> {
+        check_header(
+            headers,
+            "X-Hub-Signature",
+            &format!("sha256={}", hmac_sha256_hex(secrets, body)),
+        )
+    }
+
+    fn parse(
+        &self,
+        event_type: &str,
+        body: &[u8],
+        settings: &RepositoryConfig,
+    ) -> Result<Vec<NormalizedEvent>, Response> {
+        // Sent by "Test connection" of Bitbucket Server
+        if event_type.eq("diagnostics:ping") {
+            return Err(Response::new_ok());
+        }
+        match Self::parse_event(event_type, body)? {
+            BitbucketEvent::Push(events) => Ok(events
+                .into_iter()
+                .filter(|x| is_push_enabled(settings, x.kind(), x.created()))
+                .map(|x| NormalizedEvent::Notify(Box::new(x)))
+                .collect()),
+            BitbucketEvent::PullRequest(event) => {
+                if !settings
+                    .pull_request_actions()
+                    .iter()
+                    .any(|x| x.eq(event.action_kind()))
+                {
+                    return Err(Response::reason(204, "Skipped."));
+                }
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+        }
+    }
+}
+
+/// Bitbucket Cloud and Bitbucket Server payloads, normalized by `X-Event-Key`.
+#[derive(Debug)]
+pub enum BitbucketEvent {
+    /// Cloud may carry several ref changes in a single push, one event per change.
+    Push(Vec<BitbucketPushEvent>),
+    PullRequest(BitbucketPullRequestEvent),
+}
+
+impl BitbucketEvent {
+    /// `Ok(None)` if the event key is not supported.
+    pub fn parse(event_key: &str, body: &[u8]) -> serde_json::Result<Option<Self>> {
+        Ok(Some(match event_key {
+            "repo:push" => Self::Push(serde_json::from_slice::<CloudPushPayload>(body)?.into()),
+            "repo:refs_changed" => {
+                Self::Push(serde_json::from_slice::<ServerRefsChangedPayload>(body)?.into())
+            }
+            _ => {
+                if let Some(action) = event_key.strip_prefix("pullrequest:") {
+                    let payload = serde_json::from_slice::<CloudPullRequestPayload>(body)?;
+                    Self::PullRequest(payload.into_event(action))
+                } else if let Some(action) = event_key.strip_prefix("pr:") {
+                    let payload = serde_json::from_slice::<ServerPullRequestPayload>(body)?;
+                    Self::PullRequest(payload.into_event(action))
+                } else {
+                    return Ok(None);
+                }
+            }
+        }))
+    }
+
+    /// Empty if a push contains no change.
+    pub fn get_full_name(&self) -> &str {
+        match self {
+            Self::Push(events) => events.first().map_or("", |x| x.get_full_name()),
+            Self::PullRequest(event) => event.get_full_name(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct BitbucketPushEvent {
+    repository: String,
+    actor: String,
+    ref_name: String,
+    tag: bool,
+    before: Option<String>,
+    after: Option<String>,
+    forced: bool,
+    commits: Vec<Commit>,
+    // Bitbucket Server does not send any link
+    compare_url: Option<String>,
+}
+
+impl BitbucketPushEvent {
+    pub fn ref_name(&self) -> &str {
+        &self.ref_name
+    }
+    pub fn before(&self) -> &Option<String> {
+        &self.before
+    }
+    pub fn after(&self) -> &Option<String> {
+        &self.after
+    }
+    pub fn commits(&self) -> &Vec<Commit> {
+        &self.commits
+    }
+    pub fn created(&self) -> bool {
+        self.before.is_none()
+    }
+    pub fn deleted(&self) -> bool {
+        self.after.is_none()
+    }
+
+    pub fn kind(&self) -> PushKind {
+        if self.tag {
+            PushKind::Tag
+        } else if self.deleted() {
+            PushKind::BranchDeleted
+        } else if self.created() {
+            PushKind::BranchCreated
+        } else if self.forced {
+            PushKind::ForcePushed
+        } else {
+            PushKind::Commits
+        }
+    }
+
+    fn format_commits(&self) -> String {
+        self.commits()
+            .iter()
+            .map(|x| x.display(true))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+fn link(text: &str, url: &Option<String>) -> String {
+    match url {
+        Some(url) => format!("<a href=\"{}\">{}</a>", url, text),
+        None => text.to_string(),
+    }
+}
+
+fn short_hash(hash: &Option<String>) -> &str {
+    hash.as_deref().map_or("", datastructures::short_hash)
+}
+
+impl std::fmt::Display for BitbucketPushEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let git_ref = format!("{}:{}", self.repository, escape_html(self.ref_name()));
+        match self.kind() {
+            PushKind::Tag => {
+                return write!(
+                    f,
+                    "🏷 <b>{sender}</b> {tag} <b>pushed to {repository}</b>",
+                    sender = escape_html(&self.actor),
+                    tag = link(
+                        &format!("tag {}", escape_html(self.ref_name())),
+                        &self.compare_url
+                    ),
+                    repository = self.repository,
+                );
+            }
+            PushKind::BranchCreated => write!(
+                f,
+                "🌱 <b>{sender}</b> created branch <b>{git_ref}</b>",
+                sender = escape_html(&self.actor),
+                git_ref = git_ref,
+            )?,
+            PushKind::BranchDeleted => {
+                return write!(
+                    f,
+                    "🗑 <b>{sender}</b> deleted branch <b>{git_ref}</b> (was <code>{before}</code>)",
+                    sender = escape_html(&self.actor),
+                    git_ref = git_ref,
+                    before = short_hash(self.before()),
+                );
+            }
+            PushKind::ForcePushed => write!(
+                f,
+                "⚠️ <b>{sender}</b> {forced} <b>to {git_ref}</b> (<code>{before}..{after}</code>)",
+                sender = escape_html(&self.actor),
+                forced = link("force-pushed", &self.compare_url),
+                git_ref = git_ref,
+                before = short_hash(self.before()),
+                after = short_hash(self.after()),
+            )?,
+            PushKind::Commits if self.commits().is_empty() => write!(
+                f,
+                "🔨 <b>{sender}</b> {pushed} <b>to {git_ref}</b> (<code>{before}..{after}</code>)",
+                sender = escape_html(&self.actor),
+                pushed = link("pushed", &self.compare_url),
+                git_ref = git_ref,
+                before = short_hash(self.before()),
+                after = short_hash(self.after()),
+            )?,
+            PushKind::Commits => {
+                let count = self.commits().len();
+                write!(
+                    f,
+                    "🔨 {commits} <b>to {git_ref}</b>",
+                    commits = link(
+                        &format!("{} new commit{}", count, if count == 1 { "" } else { "s" }),
+                        &self.compare_url
+                    ),
+                    git_ref = git_ref,
+                )?
+            }
+        }
+        if !self.commits().is_empty() {
+            write!(f, ":\n\n{}", self.format_commits())?;
+        }
+        Ok(())
+    }
+}
+
+impl DisplayableEvent for BitbucketPushEvent {
+    fn get_full_name(&self) -> &String {
+        &self.repository
+    }
+
+    fn branch_name(&self) -> String {
+        if self.tag {
+            String::new()
+        } else {
+            self.ref_name.clone()
+        }
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::from(self.kind())
+    }
+
+    fn event_url(&self) -> Option<String> {
+        self.compare_url.clone()
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(&self.actor)
+    }
+
+    fn event_commits(&self) -> &[Commit] {
+        &self.commits
+    }
+}
+
+#[derive(Debug)]
+pub struct BitbucketPullRequestEvent {
+    repository: String,
+    actor: String,
+    action: String,
+    id: u64,
+    title: String,
+    url: Option<String>,
+    author: String,
+    source: String,
+    destination: String,
+}
+
+impl BitbucketPullRequestEvent {
+    /// Action translated to the names used by `pull_request_actions`, e.g. `fulfilled` to `merged`.
+    pub fn action_kind(&self) -> &str {
+        match self.action.as_str() {
+            "created" => "opened",
+            "fulfilled" => "merged",
+            "rejected" | "declined" => "closed",
+            action => action,
+        }
+    }
+}
+
+impl std::fmt::Display for BitbucketPullRequestEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (emoji, verb) = match self.action_kind() {
+            "opened" => ("🔀", "opened"),
+            "merged" => ("🟣", "merged"),
+            "closed" => ("🚫", "declined"),
+            "reopened" => ("🔁", "reopened"),
+            action => ("🔀", action),
+        };
+        write!(
+            f,
+            "{emoji} <b>{sender}</b> {verb} {pull_request} <b>in {repository}</b>:\n\n\
+            <b>{title}</b>\n\
+            by <b>{author}</b>, <code>{source}</code> → <code>{destination}</code>",
+            emoji = emoji,
+            sender = escape_html(&self.actor),
+            verb = verb,
+            pull_request = link(&format!("pull request #{}", self.id), &self.url),
+            repository = self.repository,
+            title = escape_html(&self.title),
+            author = escape_html(&self.author),
+            source = self.source,
+            destination = self.destination,
+        )
+    }
+}
+
+impl DisplayableEvent for BitbucketPullRequestEvent {
+    fn get_full_name(&self) -> &String {
+        &self.repository
+    }
+
+    fn branch_name(&self) -> String {
+        self.destination.clone()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::PullRequest
+    }
+
+    fn event_url(&self) -> Option<String> {
+        self.url.clone()
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(&self.actor)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct CloudPushPayload {
+    actor: CloudUser,
+    repository: CloudRepository,
+    push: CloudPush,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct CloudPush {
+    changes: Vec<CloudChange>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct CloudChange {
+    old: Option<CloudRef>,
+    new: Option<CloudRef>,
+    #[serde(default)]
+    forced: bool,
+    #[serde(default)]
+    commits: Vec<CloudCommit>,
+    #[serde(default)]
+    links: CloudLinks,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct CloudRef {
+    #[serde(rename = "type")]
+    ref_type: String,
+    name: String,
+    target: CloudCommit,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct CloudCommit {
+    hash: String,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    links: CloudLinks,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+struct CloudLinks {
+    html: Option<CloudHref>,
+}
+
+impl CloudLinks {
+    fn html(&self) -> Option<String> {
+        self.html.as_ref().map(|x| x.href.clone())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct CloudHref {
+    href: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct CloudRepository {
+    full_name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct CloudUser {
+    display_name: String,
+}
+
+impl From<CloudPushPayload> for Vec<BitbucketPushEvent> {
+    fn from(payload: CloudPushPayload) -> Self {
+        payload
+            .push
+            .changes
+            .into_iter()
+            .filter_map(|change| {
+                let current = change.new.as_ref().or(change.old.as_ref())?;
+                Some(BitbucketPushEvent {
+                    repository: payload.repository.full_name.clone(),
+                    actor: payload.actor.display_name.clone(),
+                    ref_name: current.name.clone(),
+                    tag: current.ref_type.eq("tag"),
+                    before: change.old.as_ref().map(|x| x.target.hash.clone()),
+                    after: change.new.as_ref().map(|x| x.target.hash.clone()),
+                    forced: change.forced,
+                    compare_url: change.links.html(),
+                    commits: change
+                        .commits
+                        .into_iter()
+                        .map(|x| {
+                            let url = x.links.html().unwrap_or_default();
+                            Commit::new(x.hash, x.message, url)
+                        })
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct CloudPullRequestPayload {
+    actor: CloudUser,
+    repository: CloudRepository,
+    pullrequest: CloudPullRequest,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct CloudPullRequest {
+    id: u64,
+    title: String,
+    author: CloudUser,
+    source: CloudEndpoint,
+    destination: CloudEndpoint,
+    #[serde(default)]
+    links: CloudLinks,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct CloudEndpoint {
+    branch: CloudBranch,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct CloudBranch {
+    name: String,
+}
+
+impl CloudPullRequestPayload {
+    fn into_event(self, action: &str) -> BitbucketPullRequestEvent {
+        let pr = self.pullrequest;
+        BitbucketPullRequestEvent {
+            repository: self.repository.full_name,
+            actor: self.actor.display_name,
+            action: action.to_string(),
+            id: pr.id,
+            title: pr.title,
+            url: pr.links.html(),
+            author: pr.author.display_name,
+            source: pr.source.branch.name,
+            destination: pr.destination.branch.name,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct ServerRefsChangedPayload {
+    actor: ServerUser,
+    repository: ServerRepository,
+    changes: Vec<ServerChange>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ServerChange {
+    #[serde(rename = "ref")]
+    git_ref: ServerRef,
+    from_hash: String,
+    to_hash: String,
+    #[serde(rename = "type")]
+    change_type: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ServerRef {
+    display_id: String,
+    #[serde(rename = "type")]
+    ref_type: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct ServerRepository {
+    slug: String,
+    project: ServerProject,
+}
+
+impl ServerRepository {
+    /// `PROJECT_KEY/repository_slug`, used as `full_name` of repository.
+    fn full_name(&self) -> String {
+        format!("{}/{}", self.project.key, self.slug)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct ServerProject {
+    key: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ServerUser {
+    display_name: String,
+}
+
+impl From<ServerRefsChangedPayload> for Vec<BitbucketPushEvent> {
+    fn from(payload: ServerRefsChangedPayload) -> Self {
+        let repository = payload.repository.full_name();
+        payload
+            .changes
+            .into_iter()
+            .map(|change| BitbucketPushEvent {
+                repository: repository.clone(),
+                actor: payload.actor.display_name.clone(),
+                ref_name: change.git_ref.display_id,
+                tag: change.git_ref.ref_type.eq("TAG"),
+                before: (change.change_type != "ADD").then_some(change.from_hash),
+                after: (change.change_type != "DELETE").then_some(change.to_hash),
+                forced: false,
+                commits: vec![],
+                compare_url: None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ServerPullRequestPayload {
+    actor: ServerUser,
+    pull_request: ServerPullRequest,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ServerPullRequest {
+    id: u64,
+    title: String,
+    author: ServerParticipant,
+    from_ref: ServerPullRequestRef,
+    to_ref: ServerPullRequestRef,
+    #[serde(default)]
+    links: ServerLinks,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct ServerParticipant {
+    user: ServerUser,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ServerPullRequestRef {
+    display_id: String,
+    repository: ServerRepository,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+struct ServerLinks {
+    #[serde(rename = "self", default)]
+    self_links: Vec<CloudHref>,
+}
+
+impl ServerPullRequestPayload {
+    fn into_event(self, action: &str) -> BitbucketPullRequestEvent {
+        let pr = self.pull_request;
+        BitbucketPullRequestEvent {
+            repository: pr.to_ref.repository.full_name(),
+            actor: self.actor.display_name,
+            action: action.to_string(),
+            id: pr.id,
+            title: pr.title,
+            url: pr.links.self_links.into_iter().next().map(|x| x.href),
+            author: pr.author.user.display_name,
+            source: pr.from_ref.display_id,
+            destination: pr.to_ref.display_id,
+        }
+    }
+}
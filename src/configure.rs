@@ -15,18 +15,154 @@
  ** along with this program. If not, see <https://www.gnu.org/licenses/>.
  */
 
+use lettre::message::Mailbox;
 use log::{error, warn};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde_derive::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 use std::str::FromStr;
+use tokio_rustls::rustls::pki_types::ServerName;
 use toml::Value;
 
+const DEFAULT_PULL_REQUEST_ACTIONS: [&str; 5] =
+    ["opened", "ready_for_review", "merged", "closed", "reopened"];
+const DEFAULT_ISSUE_ACTIONS: [&str; 4] = ["opened", "closed", "reopened", "labeled"];
+const DEFAULT_DISCUSSION_ACTIONS: [&str; 3] = ["created", "answered", "category_changed"];
+const DEFAULT_COMMENT_LENGTH: usize = 300;
+const DEFAULT_EXTRA_EVENTS: [&str; 3] = ["member", "public", "repository"];
+// One week in minutes
+const DIGEST_INTERVAL_MAX: u64 = 10080;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ActionsNotify {
+    /// Only failed, cancelled or timed out runs
+    #[default]
+    Failure,
+    /// Every completed run
+    Completed,
+    /// Only when conclusion changes between failure and success
+    Changed,
+}
+
+impl FromStr for ActionsNotify {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "failure" => Ok(Self::Failure),
+            "completed" => Ok(Self::Completed),
+            "changed" => Ok(Self::Changed),
+            _ => Err(anyhow::anyhow!("Unexpected actions_notify value {:?}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderKind {
+    GitHub,
+    GitLab,
+    Gitea,
+    Bitbucket,
+}
+
+impl FromStr for ProviderKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "github" => Ok(Self::GitHub),
+            "gitlab" => Ok(Self::GitLab),
+            // Forgejo uses the same webhook format as Gitea
+            "gitea" | "forgejo" => Ok(Self::Gitea),
+            "bitbucket" => Ok(Self::Bitbucket),
+            _ => Err(anyhow::anyhow!("Unexpected provider value {:?}", s)),
+        }
+    }
+}
+
+/// Notifiers other than Telegram, receivers are set by `<name>_send_to` of repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SinkKind {
+    Discord,
+    Slack,
+    Matrix,
+    Email,
+    Teams,
+    Webhook,
+    Ntfy,
+    Gotify,
+    Irc,
+    Xmpp,
+}
+
+impl SinkKind {
+    pub const ALL: [SinkKind; 10] = [
+        Self::Discord,
+        Self::Slack,
+        Self::Matrix,
+        Self::Email,
+        Self::Teams,
+        Self::Webhook,
+        Self::Ntfy,
+        Self::Gotify,
+        Self::Irc,
+        Self::Xmpp,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Discord => "Discord",
+            Self::Slack => "Slack",
+            Self::Matrix => "Matrix",
+            Self::Email => "Email",
+            Self::Teams => "Teams",
+            Self::Webhook => "Webhook",
+            Self::Ntfy => "Ntfy",
+            Self::Gotify => "Gotify",
+            Self::Irc => "IRC",
+            Self::Xmpp => "XMPP",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SmtpSecurity {
+    #[default]
+    StartTls,
+    /// Implicit TLS, also known as SMTPS
+    Tls,
+    /// Plain text, only for local relay
+    None,
+}
+
+impl FromStr for SmtpSecurity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "starttls" => Ok(Self::StartTls),
+            "tls" => Ok(Self::Tls),
+            "none" => Ok(Self::None),
+            _ => Err(anyhow::anyhow!("Unexpected security value {:?}", s)),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone)]
 pub struct TomlConfig {
     server: TomlServer,
     telegram: TomlTelegram,
+    matrix: Option<TomlMatrix>,
+    smtp: Option<TomlSmtp>,
+    teams: Option<TomlTeams>,
+    ntfy: Option<TomlNtfy>,
+    gotify: Option<TomlGotify>,
+    irc: Option<TomlIrc>,
+    xmpp: Option<TomlXmpp>,
     repository: Option<Vec<TomlRepository>>,
+    endpoint: Option<Vec<TomlEndpoint>>,
+    webhook: Option<Vec<TomlWebhook>>,
 }
 
 impl TryFrom<&str> for TomlConfig {
@@ -57,9 +193,95 @@ impl TomlConfig {
     pub fn telegram(&self) -> &TomlTelegram {
         &self.telegram
     }
+    pub fn matrix(&self) -> &Option<TomlMatrix> {
+        &self.matrix
+    }
+    pub fn smtp(&self) -> &Option<TomlSmtp> {
+        &self.smtp
+    }
+    pub fn teams(&self) -> &Option<TomlTeams> {
+        &self.teams
+    }
+    pub fn ntfy(&self) -> &Option<TomlNtfy> {
+        &self.ntfy
+    }
+    pub fn gotify(&self) -> &Option<TomlGotify> {
+        &self.gotify
+    }
+    pub fn irc(&self) -> &Option<TomlIrc> {
+        &self.irc
+    }
+    pub fn xmpp(&self) -> &Option<TomlXmpp> {
+        &self.xmpp
+    }
     pub fn repository(&self) -> &Option<Vec<TomlRepository>> {
         &self.repository
     }
+    pub fn endpoint(&self) -> &Option<Vec<TomlEndpoint>> {
+        &self.endpoint
+    }
+    pub fn webhook(&self) -> &Option<Vec<TomlWebhook>> {
+        &self.webhook
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TomlEndpoint {
+    path: String,
+    provider: String,
+    secrets: Option<String>,
+}
+
+impl TomlEndpoint {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+    pub fn secrets(&self) -> &Option<String> {
+        &self.secrets
+    }
+}
+
+/// Path which receives webhooks, the root path detects provider by request headers.
+#[derive(Debug, Clone, Default)]
+pub struct Endpoint {
+    path: String,
+    provider: Option<ProviderKind>,
+    secrets: Option<String>,
+}
+
+impl Endpoint {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    pub fn provider(&self) -> Option<ProviderKind> {
+        self.provider
+    }
+    /// Used by repositories without their own `secrets`, instead of `server.secrets`.
+    pub fn secrets(&self) -> &Option<String> {
+        &self.secrets
+    }
+}
+
+impl TryFrom<&TomlEndpoint> for Endpoint {
+    type Error = anyhow::Error;
+
+    fn try_from(endpoint: &TomlEndpoint) -> Result<Self, Self::Error> {
+        // The root path is served by the default endpoint, axum panics on a conflicting route
+        if !endpoint.path().starts_with('/') || endpoint.path() == "/" {
+            return Err(anyhow::anyhow!(
+                "Invalid endpoint path {:?}: expect a path which starts with `/` and is not `/`",
+                endpoint.path()
+            ));
+        }
+        Ok(Self {
+            path: endpoint.path().to_string(),
+            provider: Some(ProviderKind::from_str(endpoint.provider())?),
+            secrets: endpoint.secrets().clone(),
+        })
+    }
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -152,11 +374,680 @@ impl TomlTelegram {
     }
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TomlMatrix {
+    homeserver: String,
+    access_token: String,
+    timeout: Option<u64>,
+}
+
+impl TomlMatrix {
+    pub fn homeserver(&self) -> &str {
+        &self.homeserver
+    }
+    pub fn access_token(&self) -> &str {
+        &self.access_token
+    }
+    pub fn timeout(&self) -> &Option<u64> {
+        &self.timeout
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Matrix {
+    homeserver: String,
+    access_token: String,
+    timeout: u64,
+}
+
+impl Matrix {
+    /// Base URL of homeserver, e.g. `https://matrix-client.matrix.org`
+    pub fn homeserver(&self) -> &str {
+        &self.homeserver
+    }
+    pub fn access_token(&self) -> &str {
+        &self.access_token
+    }
+    /// Request timeout in seconds
+    pub fn timeout(&self) -> u64 {
+        self.timeout
+    }
+}
+
+impl TryFrom<&TomlMatrix> for Matrix {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &TomlMatrix) -> Result<Self, Self::Error> {
+        let homeserver = value.homeserver().trim_end_matches('/');
+        let url = reqwest::Url::parse(homeserver)
+            .map_err(|e| anyhow::anyhow!("Invalid matrix homeserver {:?}: {}", homeserver, e))?;
+        if url.cannot_be_a_base() || !matches!(url.scheme(), "http" | "https") {
+            return Err(anyhow::anyhow!(
+                "Invalid matrix homeserver {:?}: expect a http(s) URL",
+                homeserver
+            ));
+        }
+        Ok(Self {
+            homeserver: homeserver.to_string(),
+            access_token: value.access_token().to_string(),
+            timeout: value.timeout().unwrap_or(10),
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TomlSmtp {
+    host: String,
+    port: Option<u16>,
+    security: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+    from: String,
+    digest_interval: Option<u64>,
+    allow_insecure_auth: Option<bool>,
+}
+
+impl TomlSmtp {
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+    pub fn port(&self) -> &Option<u16> {
+        &self.port
+    }
+    pub fn security(&self) -> &Option<String> {
+        &self.security
+    }
+    pub fn username(&self) -> &Option<String> {
+        &self.username
+    }
+    pub fn password(&self) -> &Option<String> {
+        &self.password
+    }
+    pub fn from(&self) -> &str {
+        &self.from
+    }
+    pub fn digest_interval(&self) -> &Option<u64> {
+        &self.digest_interval
+    }
+    pub fn allow_insecure_auth(&self) -> &Option<bool> {
+        &self.allow_insecure_auth
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Smtp {
+    host: String,
+    port: u16,
+    security: SmtpSecurity,
+    username: Option<String>,
+    password: String,
+    sender: Mailbox,
+    digest_interval: Option<u64>,
+}
+
+impl Smtp {
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+    pub fn security(&self) -> SmtpSecurity {
+        self.security
+    }
+    /// Authentication is skipped if `None`
+    pub fn username(&self) -> &Option<String> {
+        &self.username
+    }
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+    pub fn sender(&self) -> &Mailbox {
+        &self.sender
+    }
+    /// Minutes between digests, `None` sends an email per event.
+    pub fn digest_interval(&self) -> Option<u64> {
+        self.digest_interval
+    }
+}
+
+impl TryFrom<&TomlSmtp> for Smtp {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &TomlSmtp) -> Result<Self, Self::Error> {
+        let security = match value.security() {
+            Some(ref security) => SmtpSecurity::from_str(security)?,
+            None => SmtpSecurity::default(),
+        };
+        if security != SmtpSecurity::None {
+            ServerName::try_from(value.host())
+                .map_err(|e| anyhow::anyhow!("Invalid smtp host {:?}: {}", value.host(), e))?;
+        }
+        if security == SmtpSecurity::None
+            && value.username().is_some()
+            && !value.allow_insecure_auth().unwrap_or_default()
+        {
+            return Err(anyhow::anyhow!(
+                "Refuse to send smtp password without TLS, set allow_insecure_auth to allow it"
+            ));
+        }
+        let sender = value
+            .from()
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid smtp from {:?}: {}", value.from(), e))?;
+        if let Some(minutes) = value.digest_interval() {
+            if *minutes > DIGEST_INTERVAL_MAX {
+                return Err(anyhow::anyhow!(
+                    "Invalid smtp digest_interval {}: expect at most {} minutes",
+                    minutes,
+                    DIGEST_INTERVAL_MAX
+                ));
+            }
+        }
+        Ok(Self {
+            host: value.host().to_string(),
+            port: value.port().unwrap_or(match security {
+                SmtpSecurity::StartTls => 587,
+                SmtpSecurity::Tls => 465,
+                SmtpSecurity::None => 25,
+            }),
+            security,
+            username: value.username().clone(),
+            password: value.password().clone().unwrap_or_default(),
+            sender,
+            digest_interval: value.digest_interval().filter(|x| *x > 0),
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TomlTeams {
+    timeout: Option<u64>,
+}
+
+impl TomlTeams {
+    pub fn timeout(&self) -> &Option<u64> {
+        &self.timeout
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Teams {
+    timeout: u64,
+}
+
+impl Teams {
+    /// Request timeout in seconds
+    pub fn timeout(&self) -> u64 {
+        self.timeout
+    }
+}
+
+impl Default for Teams {
+    fn default() -> Self {
+        // Workflows may take much longer than incoming webhooks to accept a message
+        Self { timeout: 30 }
+    }
+}
+
+impl From<&TomlTeams> for Teams {
+    fn from(value: &TomlTeams) -> Self {
+        Self {
+            timeout: value.timeout().unwrap_or(Self::default().timeout),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TomlNtfy {
+    server: Option<String>,
+    token: Option<String>,
+    timeout: Option<u64>,
+}
+
+impl TomlNtfy {
+    pub fn server(&self) -> &Option<String> {
+        &self.server
+    }
+    pub fn token(&self) -> &Option<String> {
+        &self.token
+    }
+    pub fn timeout(&self) -> &Option<u64> {
+        &self.timeout
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Ntfy {
+    server: String,
+    token: Option<String>,
+    timeout: u64,
+}
+
+impl Ntfy {
+    /// Server of topics, defaults to `https://ntfy.sh`
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+    /// Access token sent as bearer token, topics are published anonymously if `None`
+    pub fn token(&self) -> &Option<String> {
+        &self.token
+    }
+    /// Request timeout in seconds
+    pub fn timeout(&self) -> u64 {
+        self.timeout
+    }
+}
+
+impl Default for Ntfy {
+    fn default() -> Self {
+        Self {
+            server: "https://ntfy.sh".to_string(),
+            token: None,
+            timeout: 10,
+        }
+    }
+}
+
+impl From<&TomlNtfy> for Ntfy {
+    fn from(value: &TomlNtfy) -> Self {
+        Self {
+            server: match value.server() {
+                Some(server) => server.trim_end_matches('/').to_string(),
+                None => Self::default().server,
+            },
+            token: value.token().clone().filter(|x| !x.is_empty()),
+            timeout: value.timeout().unwrap_or(Self::default().timeout),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TomlGotify {
+    server: String,
+    timeout: Option<u64>,
+}
+
+impl TomlGotify {
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+    pub fn timeout(&self) -> &Option<u64> {
+        &self.timeout
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Gotify {
+    server: String,
+    timeout: u64,
+}
+
+impl Gotify {
+    /// Base URL of Gotify server, e.g. `https://gotify.example.com`
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+    /// Request timeout in seconds
+    pub fn timeout(&self) -> u64 {
+        self.timeout
+    }
+}
+
+impl From<&TomlGotify> for Gotify {
+    fn from(value: &TomlGotify) -> Self {
+        Self {
+            server: value.server().trim_end_matches('/').to_string(),
+            timeout: value.timeout().unwrap_or(10),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TomlIrc {
+    server: String,
+    port: Option<u16>,
+    tls: Option<bool>,
+    nickname: String,
+    username: Option<String>,
+    realname: Option<String>,
+    password: Option<String>,
+    flood_interval: Option<u64>,
+    flood_burst: Option<u32>,
+}
+
+impl TomlIrc {
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+    pub fn port(&self) -> &Option<u16> {
+        &self.port
+    }
+    pub fn tls(&self) -> &Option<bool> {
+        &self.tls
+    }
+    pub fn nickname(&self) -> &str {
+        &self.nickname
+    }
+    pub fn username(&self) -> &Option<String> {
+        &self.username
+    }
+    pub fn realname(&self) -> &Option<String> {
+        &self.realname
+    }
+    pub fn password(&self) -> &Option<String> {
+        &self.password
+    }
+    pub fn flood_interval(&self) -> &Option<u64> {
+        &self.flood_interval
+    }
+    pub fn flood_burst(&self) -> &Option<u32> {
+        &self.flood_burst
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Irc {
+    server: String,
+    port: u16,
+    tls: bool,
+    nickname: String,
+    username: String,
+    realname: String,
+    password: Option<String>,
+    flood_interval: u64,
+    flood_burst: u32,
+}
+
+impl Irc {
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+    pub fn tls(&self) -> bool {
+        self.tls
+    }
+    pub fn nickname(&self) -> &str {
+        &self.nickname
+    }
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+    pub fn realname(&self) -> &str {
+        &self.realname
+    }
+    /// Server password sent by `PASS`, also used to identify by some networks and bouncers
+    pub fn password(&self) -> &Option<String> {
+        &self.password
+    }
+    /// Milliseconds each line costs
+    pub fn flood_interval(&self) -> u64 {
+        self.flood_interval
+    }
+    /// Lines which can be sent at once
+    pub fn flood_burst(&self) -> u32 {
+        self.flood_burst
+    }
+}
+
+impl From<&TomlIrc> for Irc {
+    fn from(value: &TomlIrc) -> Self {
+        let tls = value.tls().unwrap_or(true);
+        Self {
+            server: value.server().to_string(),
+            port: value.port().unwrap_or(if tls { 6697 } else { 6667 }),
+            tls,
+            nickname: value.nickname().to_string(),
+            username: value
+                .username()
+                .clone()
+                .unwrap_or_else(|| value.nickname().to_string()),
+            realname: value
+                .realname()
+                .clone()
+                .unwrap_or_else(|| "GitHub webhook notification".to_string()),
+            password: value.password().clone().filter(|x| !x.is_empty()),
+            flood_interval: value.flood_interval().unwrap_or(2000),
+            flood_burst: value.flood_burst().unwrap_or(4),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TomlXmpp {
+    jid: String,
+    password: String,
+    server: Option<String>,
+    port: Option<u16>,
+    starttls: Option<bool>,
+    allow_insecure_auth: Option<bool>,
+    nickname: Option<String>,
+    xhtml: Option<bool>,
+}
+
+impl TomlXmpp {
+    pub fn jid(&self) -> &str {
+        &self.jid
+    }
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+    pub fn server(&self) -> &Option<String> {
+        &self.server
+    }
+    pub fn port(&self) -> &Option<u16> {
+        &self.port
+    }
+    pub fn starttls(&self) -> &Option<bool> {
+        &self.starttls
+    }
+    pub fn allow_insecure_auth(&self) -> &Option<bool> {
+        &self.allow_insecure_auth
+    }
+    pub fn nickname(&self) -> &Option<String> {
+        &self.nickname
+    }
+    pub fn xhtml(&self) -> &Option<bool> {
+        &self.xhtml
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Xmpp {
+    username: String,
+    domain: String,
+    password: String,
+    server: String,
+    port: u16,
+    starttls: bool,
+    nickname: String,
+    xhtml: bool,
+}
+
+impl Xmpp {
+    /// Local part of JID
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+    /// Host to connect, defaults to domain of JID
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+    pub fn starttls(&self) -> bool {
+        self.starttls
+    }
+    /// Nickname in rooms
+    pub fn nickname(&self) -> &str {
+        &self.nickname
+    }
+    /// Send XHTML-IM along with plain text
+    pub fn xhtml(&self) -> bool {
+        self.xhtml
+    }
+}
+
+impl TryFrom<&TomlXmpp> for Xmpp {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &TomlXmpp) -> Result<Self, Self::Error> {
+        // Resource is bound by server
+        let jid = value.jid().split('/').next().unwrap_or_default();
+        let (username, domain) = jid
+            .split_once('@')
+            .filter(|(username, domain)| !username.is_empty() && !domain.is_empty())
+            .ok_or_else(|| {
+                anyhow::anyhow!("Invalid xmpp jid {:?}: expect user@domain", value.jid())
+            })?;
+        let starttls = value.starttls().unwrap_or(true);
+        if !starttls && !value.allow_insecure_auth().unwrap_or_default() {
+            return Err(anyhow::anyhow!(
+                "Refuse to send xmpp password without TLS, set allow_insecure_auth to allow it"
+            ));
+        }
+        Ok(Self {
+            username: username.to_string(),
+            domain: domain.to_string(),
+            password: value.password().to_string(),
+            server: value.server().clone().unwrap_or_else(|| domain.to_string()),
+            port: value.port().unwrap_or(5222),
+            starttls,
+            nickname: value
+                .nickname()
+                .clone()
+                .unwrap_or_else(|| username.to_string()),
+            xhtml: value.xhtml().unwrap_or(true),
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TomlWebhook {
+    name: String,
+    url: String,
+    secrets: Option<String>,
+    signature_header: Option<String>,
+    headers: Option<BTreeMap<String, String>>,
+    timeout: Option<u64>,
+}
+
+impl TomlWebhook {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+    pub fn secrets(&self) -> &Option<String> {
+        &self.secrets
+    }
+    pub fn signature_header(&self) -> &Option<String> {
+        &self.signature_header
+    }
+    pub fn headers(&self) -> &Option<BTreeMap<String, String>> {
+        &self.headers
+    }
+    pub fn timeout(&self) -> &Option<u64> {
+        &self.timeout
+    }
+}
+
+/// Target of the generic webhook sink, referred by `webhook_send_to` of repository.
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    url: String,
+    secrets: Option<String>,
+    signature_header: HeaderName,
+    headers: HeaderMap,
+    timeout: u64,
+}
+
+impl Webhook {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+    /// Body is not signed if `None`
+    pub fn secrets(&self) -> &Option<String> {
+        &self.secrets
+    }
+    pub fn signature_header(&self) -> &HeaderName {
+        &self.signature_header
+    }
+    /// Extra headers sent with every request
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+    /// Request timeout in seconds
+    pub fn timeout(&self) -> u64 {
+        self.timeout
+    }
+}
+
+impl TryFrom<&TomlWebhook> for Webhook {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &TomlWebhook) -> Result<Self, Self::Error> {
+        let header_name = |name: &str| {
+            HeaderName::from_str(name).map_err(|e| {
+                anyhow::anyhow!(
+                    "Invalid header {:?} of webhook {:?}: {}",
+                    name,
+                    value.name(),
+                    e
+                )
+            })
+        };
+        let mut headers = HeaderMap::new();
+        for (name, header) in value.headers().iter().flatten() {
+            let header = HeaderValue::from_str(header).map_err(|e| {
+                anyhow::anyhow!(
+                    "Invalid value of header {:?} of webhook {:?}: {}",
+                    name,
+                    value.name(),
+                    e
+                )
+            })?;
+            headers.insert(header_name(name)?, header);
+        }
+        Ok(Self {
+            url: value.url().to_string(),
+            secrets: value.secrets().clone().filter(|x| !x.is_empty()),
+            signature_header: header_name(
+                value
+                    .signature_header()
+                    .as_deref()
+                    .unwrap_or("X-Webhook-Signature-256"),
+            )?,
+            headers,
+            timeout: value.timeout().unwrap_or(10),
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     server: Server,
     telegram: Telegram,
+    matrix: Option<Matrix>,
+    smtp: Option<Smtp>,
+    teams: Teams,
+    ntfy: Ntfy,
+    gotify: Option<Gotify>,
+    irc: Option<Irc>,
+    xmpp: Option<Xmpp>,
     repo_mapping: HashMap<String, Repository>,
+    endpoints: Vec<Endpoint>,
+    webhooks: HashMap<String, Webhook>,
 }
 
 impl Config {
@@ -166,6 +1057,60 @@ impl Config {
     pub fn telegram(&self) -> &Telegram {
         &self.telegram
     }
+    pub fn matrix(&self) -> &Option<Matrix> {
+        &self.matrix
+    }
+    pub fn smtp(&self) -> &Option<Smtp> {
+        &self.smtp
+    }
+    pub fn teams(&self) -> &Teams {
+        &self.teams
+    }
+    pub fn ntfy(&self) -> &Ntfy {
+        &self.ntfy
+    }
+    pub fn gotify(&self) -> &Option<Gotify> {
+        &self.gotify
+    }
+    pub fn irc(&self) -> &Option<Irc> {
+        &self.irc
+    }
+    pub fn xmpp(&self) -> &Option<Xmpp> {
+        &self.xmpp
+    }
+    /// Channels of all repositories, joined once connected to IRC
+    pub fn irc_channels(&self) -> Vec<String> {
+        self.all_receivers(SinkKind::Irc)
+    }
+    /// Addresses of all repositories, rooms are joined once connected to XMPP
+    pub fn xmpp_addresses(&self) -> Vec<String> {
+        self.all_receivers(SinkKind::Xmpp)
+    }
+
+    /// Whether any repository sends to the sink
+    pub fn is_sink_used(&self, kind: SinkKind) -> bool {
+        self.mapping()
+            .values()
+            .any(|x| !x.sink_send_to(kind).is_empty())
+    }
+
+    fn all_receivers(&self, kind: SinkKind) -> Vec<String> {
+        let mut receivers = self
+            .mapping()
+            .values()
+            .flat_map(|x| x.sink_send_to(kind).iter().cloned())
+            .collect::<Vec<String>>();
+        receivers.sort();
+        receivers.dedup();
+        receivers
+    }
+    pub fn endpoints(&self) -> &Vec<Endpoint> {
+        &self.endpoints
+    }
+    /// `[[webhook]]` targets by name
+    pub fn webhooks(&self) -> &HashMap<String, Webhook> {
+        &self.webhooks
+    }
 
     #[allow(dead_code)]
     #[deprecated(
@@ -181,7 +1126,7 @@ impl Config {
 
     pub fn new<P: AsRef<Path>>(path: P) -> anyhow::Result<Config> {
         let config = TomlConfig::new(path)?;
-        Ok(Self::from(&config))
+        Self::try_from(&config)
     }
 
     pub fn fetch_repository_configure(&self, branch_name: &str) -> Repository {
@@ -189,6 +1134,7 @@ impl Config {
         match conf {
             None => RepositoryBuilder::new()
                 .set_send_to(self.telegram().send_to().clone())
+                .set_security_send_to(self.telegram().send_to().clone())
                 .set_secrets(self.server().secrets())
                 .set_is_default(true)
                 .build(),
@@ -197,34 +1143,101 @@ impl Config {
     }
 
     pub fn build_repository_from_configure(
-        default_secret: &String,
-        default_target: &Vec<i64>,
+        default_secret: &str,
+        default_target: &[i64],
         repository: &TomlRepository,
-    ) -> Repository {
+    ) -> anyhow::Result<Repository> {
         let secrets = match repository.secrets() {
-            None => default_secret.clone(),
+            None => default_secret.to_string(),
             Some(s) => s.clone(),
         };
         let send_to = match repository.send_to() {
-            None => default_target.clone(),
+            None => default_target.to_vec(),
+            Some(v) => parse_value(v),
+        };
+        let security_send_to = match repository.security_send_to() {
+            None => send_to.clone(),
             Some(v) => parse_value(v),
         };
-        RepositoryBuilder::new()
+        // Defaults of options are set by `RepositoryBuilder::new`, only overridden if set
+        let mut builder = RepositoryBuilder::new();
+        if let Some(actions) = repository.pull_request_actions() {
+            builder.set_pull_request_actions(actions.clone());
+        }
+        if let Some(actions) = repository.issue_actions() {
+            builder.set_issue_actions(actions.clone());
+        }
+        if let Some(actions) = repository.discussion_actions() {
+            builder.set_discussion_actions(actions.clone());
+        }
+        if let Some(length) = repository.comment_length() {
+            builder.set_comment_length(*length);
+        }
+        if let Some(notify) = repository.notify_tag_push() {
+            builder.set_notify_tag_push(*notify);
+        }
+        if let Some(notify) = repository.notify_branch_create() {
+            builder.set_notify_branch_create(*notify);
+        }
+        if let Some(notify) = repository.notify_branch_delete() {
+            builder.set_notify_branch_delete(*notify);
+        }
+        if let Some(notify) = repository.notify_force_push() {
+            builder.set_notify_force_push(*notify);
+        }
+        if let Some(forward) = repository.forward_unknown_events() {
+            builder.set_forward_unknown_events(*forward);
+        }
+        if let Some(environments) = repository.deployment_environments() {
+            builder.set_deployment_environments(environments.clone());
+        }
+        if let Some(events) = repository.extra_events() {
+            builder.set_extra_events(events.clone());
+        }
+        if let Some(ref mode) = repository.actions_notify() {
+            builder.set_actions_notify(ActionsNotify::from_str(mode)?);
+        }
+        // Checked here so a typo is reported at startup instead of on every email
+        for address in repository.sink_send_to(SinkKind::Email).iter().flatten() {
+            address.parse::<Mailbox>().map_err(|e| {
+                anyhow::anyhow!(
+                    "Invalid email_send_to {:?} of repository {:?}: {}",
+                    address,
+                    repository.full_name(),
+                    e
+                )
+            })?;
+        }
+        if let Some(branch_ignore) = repository.branch_ignore() {
+            builder.set_branch_ignore(branch_ignore.clone());
+        }
+        Ok(builder
             .set_secrets(&secrets)
-            .set_branch_ignore(repository.branch_ignore.clone().unwrap_or_default())
             .set_send_to(send_to)
+            .set_security_send_to(security_send_to)
+            .set_custom_secrets(repository.secrets().is_some())
+            .set_sink_send_to(repository.sinks_send_to())
             .set_is_default(false)
-            .build()
+            .build())
     }
 }
 
-impl From<&TomlConfig> for Config {
-    fn from(config: &TomlConfig) -> Self {
+impl TryFrom<&TomlConfig> for Config {
+    type Error = anyhow::Error;
+
+    fn try_from(config: &TomlConfig) -> Result<Self, Self::Error> {
         let real_secret = config.server().secrets().clone().unwrap_or_default();
         let real_receiver = parse_value(config.telegram().send_to());
-        Self {
+        Ok(Self {
             server: Server::from(config.server()),
             telegram: Telegram::from(config.telegram()),
+            matrix: config.matrix().as_ref().map(Matrix::try_from).transpose()?,
+            smtp: config.smtp().as_ref().map(Smtp::try_from).transpose()?,
+            teams: config.teams().as_ref().map(Teams::from).unwrap_or_default(),
+            ntfy: config.ntfy().as_ref().map(Ntfy::from).unwrap_or_default(),
+            gotify: config.gotify().as_ref().map(Gotify::from),
+            irc: config.irc().as_ref().map(Irc::from),
+            xmpp: config.xmpp().as_ref().map(Xmpp::try_from).transpose()?,
             repo_mapping: {
                 let mut m = HashMap::new();
                 if let Some(repositories) = config.repository() {
@@ -235,13 +1248,31 @@ impl From<&TomlConfig> for Config {
                                 &real_secret,
                                 &real_receiver,
                                 repository,
-                            ),
+                            )?,
                         );
                     }
                 }
                 m
             },
-        }
+            endpoints: {
+                let mut paths = HashSet::new();
+                config
+                    .endpoint()
+                    .iter()
+                    .flatten()
+                    .map(|x| match paths.insert(x.path()) {
+                        true => Endpoint::try_from(x),
+                        false => Err(anyhow::anyhow!("Duplicate endpoint path {:?}", x.path())),
+                    })
+                    .collect::<anyhow::Result<_>>()?
+            },
+            webhooks: config
+                .webhook()
+                .iter()
+                .flatten()
+                .map(|x| Ok((x.name().to_string(), Webhook::try_from(x)?)))
+                .collect::<anyhow::Result<_>>()?,
+        })
     }
 }
 
@@ -270,8 +1301,8 @@ impl From<&TomlServer> for Server {
         }
         Self {
             bind: format!("{}:{}", s.bind(), s.port()),
-            secrets: s.secrets().clone().unwrap_or_else(|| "".to_string()),
-            token: s.token().clone().unwrap_or_else(|| "".to_string()),
+            secrets: s.secrets().clone().unwrap_or_default(),
+            token: s.token().clone().unwrap_or_default(),
         }
     }
 }
@@ -292,8 +1323,31 @@ impl Server {
 pub struct TomlRepository {
     full_name: String,
     send_to: Option<Value>,
+    security_send_to: Option<Value>,
     branch_ignore: Option<Vec<String>>,
     secrets: Option<String>,
+    pull_request_actions: Option<Vec<String>>,
+    issue_actions: Option<Vec<String>>,
+    discussion_actions: Option<Vec<String>>,
+    comment_length: Option<usize>,
+    notify_tag_push: Option<bool>,
+    notify_branch_create: Option<bool>,
+    notify_branch_delete: Option<bool>,
+    notify_force_push: Option<bool>,
+    actions_notify: Option<String>,
+    extra_events: Option<Vec<String>>,
+    deployment_environments: Option<Vec<String>>,
+    forward_unknown_events: Option<bool>,
+    discord_send_to: Option<Vec<String>>,
+    slack_send_to: Option<Vec<String>>,
+    matrix_send_to: Option<Vec<String>>,
+    email_send_to: Option<Vec<String>>,
+    teams_send_to: Option<Vec<String>>,
+    webhook_send_to: Option<Vec<String>>,
+    ntfy_send_to: Option<Vec<String>>,
+    gotify_send_to: Option<Vec<String>>,
+    irc_send_to: Option<Vec<String>>,
+    xmpp_send_to: Option<Vec<String>>,
 }
 
 impl TomlRepository {
@@ -303,12 +1357,77 @@ impl TomlRepository {
     pub fn send_to(&self) -> &Option<Value> {
         &self.send_to
     }
+    pub fn security_send_to(&self) -> &Option<Value> {
+        &self.security_send_to
+    }
     pub fn branch_ignore(&self) -> &Option<Vec<String>> {
         &self.branch_ignore
     }
     pub fn secrets(&self) -> &Option<String> {
         &self.secrets
     }
+    pub fn pull_request_actions(&self) -> &Option<Vec<String>> {
+        &self.pull_request_actions
+    }
+    pub fn issue_actions(&self) -> &Option<Vec<String>> {
+        &self.issue_actions
+    }
+    pub fn discussion_actions(&self) -> &Option<Vec<String>> {
+        &self.discussion_actions
+    }
+    pub fn comment_length(&self) -> &Option<usize> {
+        &self.comment_length
+    }
+    pub fn notify_tag_push(&self) -> &Option<bool> {
+        &self.notify_tag_push
+    }
+    pub fn notify_branch_create(&self) -> &Option<bool> {
+        &self.notify_branch_create
+    }
+    pub fn notify_branch_delete(&self) -> &Option<bool> {
+        &self.notify_branch_delete
+    }
+    pub fn notify_force_push(&self) -> &Option<bool> {
+        &self.notify_force_push
+    }
+    pub fn actions_notify(&self) -> &Option<String> {
+        &self.actions_notify
+    }
+    pub fn extra_events(&self) -> &Option<Vec<String>> {
+        &self.extra_events
+    }
+    pub fn deployment_environments(&self) -> &Option<Vec<String>> {
+        &self.deployment_environments
+    }
+    pub fn forward_unknown_events(&self) -> &Option<bool> {
+        &self.forward_unknown_events
+    }
+    pub fn sink_send_to(&self, kind: SinkKind) -> &Option<Vec<String>> {
+        match kind {
+            SinkKind::Discord => &self.discord_send_to,
+            SinkKind::Slack => &self.slack_send_to,
+            SinkKind::Matrix => &self.matrix_send_to,
+            SinkKind::Email => &self.email_send_to,
+            SinkKind::Teams => &self.teams_send_to,
+            SinkKind::Webhook => &self.webhook_send_to,
+            SinkKind::Ntfy => &self.ntfy_send_to,
+            SinkKind::Gotify => &self.gotify_send_to,
+            SinkKind::Irc => &self.irc_send_to,
+            SinkKind::Xmpp => &self.xmpp_send_to,
+        }
+    }
+    /// Receivers of sinks which are set in this repository
+    pub fn sinks_send_to(&self) -> HashMap<SinkKind, Vec<String>> {
+        SinkKind::ALL
+            .into_iter()
+            .filter_map(|kind| {
+                self.sink_send_to(kind)
+                    .clone()
+                    .filter(|x| !x.is_empty())
+                    .map(|x| (kind, x))
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -317,8 +1436,25 @@ pub struct Repository {
     is_default: bool,
     //full_name: String,
     send_to: Vec<i64>,
+    security_send_to: Vec<i64>,
     branch_ignore: Vec<String>,
     secrets: String,
+    pull_request_actions: Vec<String>,
+    issue_actions: Vec<String>,
+    discussion_actions: Vec<String>,
+    comment_length: usize,
+    notify_tag_push: bool,
+    notify_branch_create: bool,
+    notify_branch_delete: bool,
+    notify_force_push: bool,
+    actions_notify: ActionsNotify,
+    extra_events: Vec<String>,
+    deployment_environments: Vec<String>,
+    forward_unknown_events: bool,
+    // Whether `secrets` is set in this repository rather than inherited
+    custom_secrets: bool,
+    // Receivers of each sink, sinks without receivers are absent
+    sink_send_to: HashMap<SinkKind, Vec<String>>,
 }
 
 impl Repository {
@@ -328,45 +1464,95 @@ impl Repository {
     pub fn send_to(&self) -> &Vec<i64> {
         &self.send_to
     }
+    pub fn security_send_to(&self) -> &Vec<i64> {
+        &self.security_send_to
+    }
     pub fn branch_ignore(&self) -> &Vec<String> {
         &self.branch_ignore
     }
     pub fn secrets(&self) -> &String {
         &self.secrets
     }
+    /// Replace inherited secrets, secrets set in repository are kept.
+    pub fn set_default_secrets(&mut self, secrets: &str) {
+        if !self.custom_secrets {
+            self.secrets = secrets.to_string();
+        }
+    }
+    pub fn pull_request_actions(&self) -> &Vec<String> {
+        &self.pull_request_actions
+    }
+    pub fn issue_actions(&self) -> &Vec<String> {
+        &self.issue_actions
+    }
+    pub fn discussion_actions(&self) -> &Vec<String> {
+        &self.discussion_actions
+    }
+    pub fn comment_length(&self) -> usize {
+        self.comment_length
+    }
+    pub fn notify_tag_push(&self) -> bool {
+        self.notify_tag_push
+    }
+    pub fn notify_branch_create(&self) -> bool {
+        self.notify_branch_create
+    }
+    pub fn notify_branch_delete(&self) -> bool {
+        self.notify_branch_delete
+    }
+    pub fn notify_force_push(&self) -> bool {
+        self.notify_force_push
+    }
+    pub fn actions_notify(&self) -> ActionsNotify {
+        self.actions_notify
+    }
+    pub fn extra_events(&self) -> &Vec<String> {
+        &self.extra_events
+    }
+    /// Empty `deployment_environments` means all environments are enabled.
+    pub fn is_deployment_environment_enabled(&self, environment: &str) -> bool {
+        self.deployment_environments.is_empty()
+            || self
+                .deployment_environments
+                .iter()
+                .any(|x| x.eq(environment))
+    }
+    pub fn forward_unknown_events(&self) -> bool {
+        self.forward_unknown_events
+    }
+    /// Receivers of sink, e.g. Discord webhook URLs, Matrix rooms or names of `[[webhook]]` targets
+    pub fn sink_send_to(&self, kind: SinkKind) -> &[String] {
+        self.sink_send_to
+            .get(&kind)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
     #[cfg(test)]
     pub fn is_default(&self) -> bool {
         self.is_default
     }
 }
 
-impl From<&TomlRepository> for Repository {
-    fn from(repo: &TomlRepository) -> Self {
-        Self {
-            //full_name: repo.full_name().clone(),
-            send_to: match repo.send_to() {
-                None => vec![],
-                Some(v) => parse_value(v),
-            },
-            branch_ignore: match repo.branch_ignore() {
-                Some(v) => v.clone(),
-                None => vec![],
-            },
-            secrets: match repo.secrets() {
-                None => "".to_string(),
-                Some(ref secret) => secret.clone(),
-            },
-            #[cfg(test)]
-            is_default: true,
-        }
-    }
-}
-
 #[derive(Debug, Default, Clone)]
 pub struct RepositoryBuilder {
     send_to: Vec<i64>,
+    security_send_to: Vec<i64>,
     branch_ignore: Vec<String>,
     secrets: String,
+    pull_request_actions: Vec<String>,
+    issue_actions: Vec<String>,
+    discussion_actions: Vec<String>,
+    comment_length: usize,
+    notify_tag_push: bool,
+    notify_branch_create: bool,
+    notify_branch_delete: bool,
+    notify_force_push: bool,
+    actions_notify: ActionsNotify,
+    extra_events: Vec<String>,
+    deployment_environments: Vec<String>,
+    forward_unknown_events: bool,
+    custom_secrets: bool,
+    sink_send_to: HashMap<SinkKind, Vec<String>>,
     #[cfg(test)]
     is_default: bool,
 }
@@ -376,12 +1562,75 @@ impl RepositoryBuilder {
         self.send_to = send_to;
         self
     }
+    pub fn set_security_send_to(&mut self, security_send_to: Vec<i64>) -> &mut Self {
+        self.security_send_to = security_send_to;
+        self
+    }
     pub fn set_branch_ignore(&mut self, branch_ignore: Vec<String>) -> &mut Self {
         self.branch_ignore = branch_ignore;
         self
     }
-    pub fn set_secrets(&mut self, secrets: &String) -> &mut Self {
-        self.secrets = secrets.clone();
+    pub fn set_secrets(&mut self, secrets: &str) -> &mut Self {
+        self.secrets = secrets.to_string();
+        self
+    }
+    pub fn set_pull_request_actions(&mut self, pull_request_actions: Vec<String>) -> &mut Self {
+        self.pull_request_actions = pull_request_actions;
+        self
+    }
+    pub fn set_issue_actions(&mut self, issue_actions: Vec<String>) -> &mut Self {
+        self.issue_actions = issue_actions;
+        self
+    }
+    pub fn set_discussion_actions(&mut self, discussion_actions: Vec<String>) -> &mut Self {
+        self.discussion_actions = discussion_actions;
+        self
+    }
+    pub fn set_comment_length(&mut self, comment_length: usize) -> &mut Self {
+        self.comment_length = comment_length;
+        self
+    }
+    pub fn set_notify_tag_push(&mut self, notify_tag_push: bool) -> &mut Self {
+        self.notify_tag_push = notify_tag_push;
+        self
+    }
+    pub fn set_notify_branch_create(&mut self, notify_branch_create: bool) -> &mut Self {
+        self.notify_branch_create = notify_branch_create;
+        self
+    }
+    pub fn set_notify_branch_delete(&mut self, notify_branch_delete: bool) -> &mut Self {
+        self.notify_branch_delete = notify_branch_delete;
+        self
+    }
+    pub fn set_notify_force_push(&mut self, notify_force_push: bool) -> &mut Self {
+        self.notify_force_push = notify_force_push;
+        self
+    }
+    pub fn set_actions_notify(&mut self, actions_notify: ActionsNotify) -> &mut Self {
+        self.actions_notify = actions_notify;
+        self
+    }
+    pub fn set_extra_events(&mut self, extra_events: Vec<String>) -> &mut Self {
+        self.extra_events = extra_events;
+        self
+    }
+    pub fn set_deployment_environments(
+        &mut self,
+        deployment_environments: Vec<String>,
+    ) -> &mut Self {
+        self.deployment_environments = deployment_environments;
+        self
+    }
+    pub fn set_forward_unknown_events(&mut self, forward_unknown_events: bool) -> &mut Self {
+        self.forward_unknown_events = forward_unknown_events;
+        self
+    }
+    pub fn set_custom_secrets(&mut self, custom_secrets: bool) -> &mut Self {
+        self.custom_secrets = custom_secrets;
+        self
+    }
+    pub fn set_sink_send_to(&mut self, sink_send_to: HashMap<SinkKind, Vec<String>>) -> &mut Self {
+        self.sink_send_to = sink_send_to;
         self
     }
     #[cfg(test)]
@@ -396,17 +1645,42 @@ impl RepositoryBuilder {
     pub fn build(&self) -> Repository {
         Repository {
             send_to: self.send_to.clone(),
+            security_send_to: self.security_send_to.clone(),
             branch_ignore: self.branch_ignore.clone(),
             secrets: self.secrets.clone(),
+            pull_request_actions: self.pull_request_actions.clone(),
+            issue_actions: self.issue_actions.clone(),
+            discussion_actions: self.discussion_actions.clone(),
+            comment_length: self.comment_length,
+            notify_tag_push: self.notify_tag_push,
+            notify_branch_create: self.notify_branch_create,
+            notify_branch_delete: self.notify_branch_delete,
+            notify_force_push: self.notify_force_push,
+            actions_notify: self.actions_notify,
+            extra_events: self.extra_events.clone(),
+            deployment_environments: self.deployment_environments.clone(),
+            forward_unknown_events: self.forward_unknown_events,
+            custom_secrets: self.custom_secrets,
+            sink_send_to: self.sink_send_to.clone(),
             #[cfg(test)]
             is_default: self.is_default,
         }
     }
     pub fn new() -> Self {
         Self {
+            pull_request_actions: to_string_vec(&DEFAULT_PULL_REQUEST_ACTIONS),
+            issue_actions: to_string_vec(&DEFAULT_ISSUE_ACTIONS),
+            discussion_actions: to_string_vec(&DEFAULT_DISCUSSION_ACTIONS),
+            extra_events: to_string_vec(&DEFAULT_EXTRA_EVENTS),
+            comment_length: DEFAULT_COMMENT_LENGTH,
+            notify_force_push: true,
             #[cfg(test)]
             is_default: true,
             ..Default::default()
         }
     }
 }
+
+fn to_string_vec(v: &[&str]) -> Vec<String> {
+    v.iter().map(|x| x.to_string()).collect()
+}
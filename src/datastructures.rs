@@ -15,133 +15,3292 @@
  ** along with this program. If not, see <https://www.gnu.org/licenses/>.
  */
 
+use crate::markdown::markdown_to_telegram_html;
+use crate::sink::html_to_plain;
 use crate::{IntoResponse, StatusCode, AUTH_TOKEN};
 use axum::extract::{FromRequest, RequestParts};
 use serde_derive::{Deserialize, Serialize};
 use std::fmt::{Debug, Formatter};
 use std::ops::Index;
+use std::sync::Arc;
 
 pub trait DisplayableEvent: std::fmt::Display + Debug + Send + Sync {
     fn get_full_name(&self) -> &String;
 
+    /// Empty if this event is not related to any branch.
     fn branch_name(&self) -> String;
+
+    /// Used by sinks which color or prioritize messages, e.g. Discord.
+    fn event_kind(&self) -> EventKind {
+        EventKind::Other
+    }
+
+    /// Link to the subject of this event, e.g. the pull request.
+    fn event_url(&self) -> Option<String> {
+        None
+    }
+
+    /// Login of the user who triggered this event.
+    fn event_actor(&self) -> Option<&str> {
+        None
+    }
+
+    /// Only push events carry commits.
+    fn event_commits(&self) -> &[Commit] {
+        &[]
+    }
+}
+
+impl<F: ?Sized + Send + Sync> DisplayableEvent for Box<F>
+where
+    F: DisplayableEvent,
+{
+    fn get_full_name(&self) -> &String {
+        (**self).get_full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        (**self).branch_name()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        (**self).event_kind()
+    }
+
+    fn event_url(&self) -> Option<String> {
+        (**self).event_url()
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        (**self).event_actor()
+    }
+
+    fn event_commits(&self) -> &[Commit] {
+        (**self).event_commits()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Push,
+    Tag,
+    /// Branch created or deleted
+    Branch,
+    PullRequest,
+    Review,
+    Issue,
+    Comment,
+    Discussion,
+    Release,
+    Wiki,
+    Deployment,
+    /// Completed check, workflow or pipeline which is not failed
+    Check,
+    CheckFailure,
+    Security,
+    Star,
+    Fork,
+    /// Changes of repository itself, e.g. member or visibility
+    Repository,
+    Other,
+}
+
+impl From<PushKind> for EventKind {
+    fn from(kind: PushKind) -> Self {
+        match kind {
+            PushKind::Tag => Self::Tag,
+            PushKind::BranchCreated | PushKind::BranchDeleted => Self::Branch,
+            PushKind::ForcePushed | PushKind::Commits => Self::Push,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubEarlyParse {
+    repository: Repository,
+}
+
+impl GitHubEarlyParse {
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+
+    pub fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubPingEvent {
+    zen: String,
+}
+
+impl GitHubPingEvent {
+    pub fn zen(&self) -> &str {
+        &self.zen
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubPushEvent {
+    #[serde(rename = "ref")]
+    remote_ref: String,
+    after: String,
+    before: String,
+    #[serde(default)]
+    created: bool,
+    #[serde(default)]
+    deleted: bool,
+    #[serde(default)]
+    forced: bool,
+    commits: Vec<Commit>,
+    #[serde(alias = "compare_url")]
+    compare: String,
+    repository: Repository,
+    sender: Option<User>,
+}
+
+impl GitHubPushEvent {
+    pub fn remote_ref(&self) -> &str {
+        &self.remote_ref
+    }
+    pub fn commits(&self) -> &Vec<Commit> {
+        &self.commits
+    }
+    pub fn compare(&self) -> &str {
+        &self.compare
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn after(&self) -> &str {
+        &self.after
+    }
+    pub fn before(&self) -> &str {
+        &self.before
+    }
+    pub fn created(&self) -> bool {
+        self.created || check_0(self.before())
+    }
+    pub fn deleted(&self) -> bool {
+        self.deleted || check_0(self.after())
+    }
+    pub fn forced(&self) -> bool {
+        self.forced
+    }
+    pub fn sender(&self) -> &Option<User> {
+        &self.sender
+    }
+
+    /// Tag name if this event is pushed to a tag.
+    pub fn tag_name(&self) -> Option<&str> {
+        self.remote_ref().strip_prefix("refs/tags/")
+    }
+
+    pub fn kind(&self) -> PushKind {
+        if self.tag_name().is_some() {
+            PushKind::Tag
+        } else if self.deleted() {
+            PushKind::BranchDeleted
+        } else if self.created() {
+            PushKind::BranchCreated
+        } else if self.forced() {
+            PushKind::ForcePushed
+        } else {
+            PushKind::Commits
+        }
+    }
+
+    fn format_commits(&self) -> String {
+        self.commits()
+            .iter()
+            .map(|x| x.display(true))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn format_sender(&self) -> String {
+        match self.sender() {
+            Some(sender) => format!("<b>{}</b> ", sender),
+            None => "".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushKind {
+    Tag,
+    BranchCreated,
+    BranchDeleted,
+    ForcePushed,
+    Commits,
+}
+
+impl std::fmt::Display for GitHubPushEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let branch = self.remote_ref().rsplit_once('/').unwrap().1;
+        let git_ref = format!("{}:{}", self.repository(), branch);
+        match self.kind() {
+            PushKind::Tag => {
+                return write!(
+                    f,
+                    "🏷 {sender}<a href=\"{url}\">tag {tag}</a> <b>pushed to {repository}</b>",
+                    sender = self.format_sender(),
+                    url = self.compare(),
+                    tag = escape_html(self.tag_name().unwrap_or_default()),
+                    repository = self.repository(),
+                );
+            }
+            PushKind::BranchCreated => {
+                write!(
+                    f,
+                    "🌱 {sender}<a href=\"{url}\">created branch</a> <b>{git_ref}</b>",
+                    sender = self.format_sender(),
+                    url = self.compare(),
+                    git_ref = git_ref,
+                )?;
+                if !self.commits().is_empty() {
+                    write!(f, ":\n\n{}", self.format_commits())?;
+                }
+                return Ok(());
+            }
+            PushKind::BranchDeleted => {
+                return write!(
+                    f,
+                    "🗑 {sender}deleted branch <b>{git_ref}</b> (was <code>{before}</code>)",
+                    sender = self.format_sender(),
+                    git_ref = git_ref,
+                    before = short_hash(self.before()),
+                );
+            }
+            PushKind::ForcePushed => {
+                write!(
+                    f,
+                    "⚠️ {sender}<a href=\"{url}\">force-pushed</a> <b>to {git_ref}</b> (<code>{before}..{after}</code>)",
+                    sender = self.format_sender(),
+                    url = self.compare(),
+                    git_ref = git_ref,
+                    before = short_hash(self.before()),
+                    after = short_hash(self.after()),
+                )?;
+                if !self.commits().is_empty() {
+                    write!(f, ":\n\n{}", self.format_commits())?;
+                }
+                return Ok(());
+            }
+            PushKind::Commits => {}
+        }
+        if self.commits.len() == 1 {
+            let item = self.commits().index(0);
+            write!(
+                f,
+                "🔨 <a href=\"{url}\">{count} new commit</a> <b>to {git_ref}</b>:\n\n{commits}",
+                url = item.url(),
+                count = 1,
+                git_ref = git_ref,
+                commits = item
+            )
+        } else {
+            let l = self.format_commits();
+            write!(
+                f,
+                "🔨 <a href=\"{url}\">{count} new commits</a> <b>to {git_ref}</b>:\n\n{commits}",
+                url = self.compare(),
+                count = self.commits.len(),
+                git_ref = git_ref,
+                commits = l,
+            )
+        }
+    }
+}
+
+impl DisplayableEvent for GitHubPushEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        self.remote_ref().rsplit_once('/').unwrap().1.to_string()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::from(self.kind())
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.compare.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        self.sender.as_ref().map(User::login)
+    }
+
+    fn event_commits(&self) -> &[Commit] {
+        &self.commits
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubPullRequestEvent {
+    action: String,
+    number: u64,
+    pull_request: PullRequest,
+    repository: Repository,
+    sender: User,
+}
+
+impl GitHubPullRequestEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+    pub fn pull_request(&self) -> &PullRequest {
+        &self.pull_request
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn sender(&self) -> &User {
+        &self.sender
+    }
+
+    /// Same as `action`, except a closed pull request which has been merged is reported as `merged`.
+    pub fn action_kind(&self) -> &str {
+        if self.action.eq("closed") && self.pull_request().merged() {
+            "merged"
+        } else {
+            self.action()
+        }
+    }
+}
+
+impl std::fmt::Display for GitHubPullRequestEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let pr = self.pull_request();
+        let (emoji, verb) = match self.action_kind() {
+            "opened" => ("🔀", "opened"),
+            "ready_for_review" => ("👀", "marked as ready for review"),
+            "merged" => ("🟣", "merged"),
+            "closed" => ("🚫", "closed"),
+            "reopened" => ("🔁", "reopened"),
+            action => ("🔀", action),
+        };
+        write!(
+            f,
+            "{emoji} <b>{sender}</b> {verb} <a href=\"{url}\">pull request #{number}</a> <b>in {repository}</b>:\n\n\
+            <b>{title}</b>\n\
+            by <a href=\"{author_url}\">{author}</a>, <code>{head}</code> → <code>{base}</code>",
+            emoji = emoji,
+            sender = self.sender(),
+            verb = verb,
+            url = pr.html_url(),
+            number = self.number(),
+            repository = self.repository(),
+            title = escape_html(pr.title()),
+            author_url = pr.user().html_url(),
+            author = pr.user(),
+            head = escape_html(pr.head().label()),
+            base = escape_html(pr.base().branch()),
+        )
+    }
+}
+
+impl DisplayableEvent for GitHubPullRequestEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        self.pull_request().base().branch().to_string()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::PullRequest
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.pull_request.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct PullRequest {
+    html_url: String,
+    number: u64,
+    title: String,
+    user: User,
+    #[serde(default)]
+    merged: Option<bool>,
+    base: GitRef,
+    head: GitRef,
+}
+
+impl PullRequest {
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+    pub fn merged(&self) -> bool {
+        self.merged.unwrap_or_default()
+    }
+    pub fn base(&self) -> &GitRef {
+        &self.base
+    }
+    pub fn head(&self) -> &GitRef {
+        &self.head
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitRef {
+    label: String,
+    #[serde(rename = "ref")]
+    branch: String,
+}
+
+impl GitRef {
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+    pub fn branch(&self) -> &str {
+        &self.branch
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct User {
+    login: String,
+    // Missing in payloads from older Gitea
+    #[serde(default)]
+    html_url: String,
+}
+
+impl User {
+    pub fn login(&self) -> &str {
+        &self.login
+    }
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+}
+
+impl std::fmt::Display for User {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubPullRequestReviewEvent {
+    action: String,
+    review: Review,
+    pull_request: PullRequest,
+    repository: Repository,
+    sender: User,
+    #[serde(skip)]
+    comment_length: Option<usize>,
+}
+
+impl GitHubPullRequestReviewEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn review(&self) -> &Review {
+        &self.review
+    }
+    pub fn pull_request(&self) -> &PullRequest {
+        &self.pull_request
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn set_comment_length(&mut self, comment_length: usize) -> &mut Self {
+        self.comment_length = Some(comment_length);
+        self
+    }
+}
+
+impl std::fmt::Display for GitHubPullRequestReviewEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let review = self.review();
+        let (emoji, verb) = match review.state() {
+            "approved" => ("✅", "approved"),
+            "changes_requested" => ("🛑", "requested changes on"),
+            "dismissed" => ("🚫", "dismissed review on"),
+            _ => ("💬", "reviewed"),
+        };
+        write!(
+            f,
+            "{emoji} <b>{reviewer}</b> {verb} <a href=\"{url}\">pull request #{number}</a> <b>in {repository}</b>:\n\n<b>{title}</b>",
+            emoji = emoji,
+            reviewer = review.user(),
+            verb = verb,
+            url = review.html_url(),
+            number = self.pull_request().number(),
+            repository = self.repository(),
+            title = escape_html(self.pull_request().title()),
+        )?;
+        let body = review.body().as_deref().unwrap_or_default().trim();
+        if !body.is_empty() {
+            write!(
+                f,
+                "\n\n{}",
+                escape_html(&truncate(body, self.comment_length))
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl DisplayableEvent for GitHubPullRequestReviewEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        self.pull_request().base().branch().to_string()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Review
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.review.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Review {
+    html_url: String,
+    state: String,
+    body: Option<String>,
+    user: User,
+}
+
+impl Review {
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+    pub fn state(&self) -> &str {
+        &self.state
+    }
+    pub fn body(&self) -> &Option<String> {
+        &self.body
+    }
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubPullRequestReviewCommentEvent {
+    action: String,
+    comment: ReviewComment,
+    pull_request: PullRequest,
+    repository: Repository,
+    sender: User,
+    #[serde(skip)]
+    comment_length: Option<usize>,
+}
+
+impl GitHubPullRequestReviewCommentEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn comment(&self) -> &ReviewComment {
+        &self.comment
+    }
+    pub fn pull_request(&self) -> &PullRequest {
+        &self.pull_request
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn set_comment_length(&mut self, comment_length: usize) -> &mut Self {
+        self.comment_length = Some(comment_length);
+        self
+    }
+}
+
+impl std::fmt::Display for GitHubPullRequestReviewCommentEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let comment = self.comment();
+        write!(
+            f,
+            "💬 <b>{sender}</b> commented on <a href=\"{url}\">pull request #{number}</a> <b>in {repository}</b>:\n\n\
+            <b>{title}</b>\n<code>{location}</code>\n\n{body}",
+            sender = comment.user(),
+            url = comment.html_url(),
+            number = self.pull_request().number(),
+            repository = self.repository(),
+            title = escape_html(self.pull_request().title()),
+            location = escape_html(&comment.location()),
+            body = escape_html(&truncate(comment.body(), self.comment_length)),
+        )
+    }
+}
+
+impl DisplayableEvent for GitHubPullRequestReviewCommentEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        self.pull_request().base().branch().to_string()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Comment
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.comment.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ReviewComment {
+    html_url: String,
+    body: String,
+    path: String,
+    line: Option<u64>,
+    original_line: Option<u64>,
+    user: User,
+}
+
+impl ReviewComment {
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    /// `path:line`, line is taken from the original diff if the comment is outdated.
+    pub fn location(&self) -> String {
+        match self.line.or(self.original_line) {
+            Some(line) => format!("{}:{}", self.path(), line),
+            None => self.path().to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubIssuesEvent {
+    action: String,
+    issue: Issue,
+    label: Option<Label>,
+    repository: Repository,
+    sender: User,
+}
+
+impl GitHubIssuesEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn issue(&self) -> &Issue {
+        &self.issue
+    }
+    pub fn label(&self) -> &Option<Label> {
+        &self.label
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn sender(&self) -> &User {
+        &self.sender
+    }
+}
+
+impl std::fmt::Display for GitHubIssuesEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (emoji, verb) = match self.action() {
+            "opened" => ("📝", "opened"),
+            "closed" => ("✅", "closed"),
+            "reopened" => ("🔁", "reopened"),
+            "labeled" => ("🏷", "labeled"),
+            action => ("📝", action),
+        };
+        write!(
+            f,
+            "{emoji} <b>{sender}</b> {verb} <a href=\"{url}\">issue #{number}</a> <b>in {repository}</b>:\n\n<b>{title}</b>",
+            emoji = emoji,
+            sender = self.sender(),
+            verb = verb,
+            url = self.issue().html_url(),
+            number = self.issue().number(),
+            repository = self.repository(),
+            title = escape_html(self.issue().title()),
+        )?;
+        if let Some(label) = self.label() {
+            write!(f, "\nLabel: <code>{}</code>", escape_html(label.name()))?;
+        }
+        Ok(())
+    }
+}
+
+impl DisplayableEvent for GitHubIssuesEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        String::new()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Issue
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.issue.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubIssueCommentEvent {
+    action: String,
+    issue: Issue,
+    comment: Comment,
+    repository: Repository,
+    sender: User,
+    #[serde(skip)]
+    comment_length: Option<usize>,
+}
+
+impl GitHubIssueCommentEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn issue(&self) -> &Issue {
+        &self.issue
+    }
+    pub fn comment(&self) -> &Comment {
+        &self.comment
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn sender(&self) -> &User {
+        &self.sender
+    }
+    pub fn set_comment_length(&mut self, comment_length: usize) -> &mut Self {
+        self.comment_length = Some(comment_length);
+        self
+    }
+}
+
+impl std::fmt::Display for GitHubIssueCommentEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let target = if self.issue().is_pull_request() {
+            "pull request"
+        } else {
+            "issue"
+        };
+        write!(
+            f,
+            "💬 <b>{sender}</b> commented on <a href=\"{url}\">{target} #{number}</a> <b>in {repository}</b>:\n\n<b>{title}</b>\n\n{body}",
+            sender = self.sender(),
+            url = self.comment().html_url(),
+            target = target,
+            number = self.issue().number(),
+            repository = self.repository(),
+            title = escape_html(self.issue().title()),
+            body = escape_html(&truncate(self.comment().body(), self.comment_length)),
+        )
+    }
+}
+
+impl DisplayableEvent for GitHubIssueCommentEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        String::new()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Comment
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.comment.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubReleaseEvent {
+    action: String,
+    release: Release,
+    repository: Repository,
+    sender: User,
+    #[serde(skip)]
+    notes_length: Option<usize>,
+}
+
+impl GitHubReleaseEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn release(&self) -> &Release {
+        &self.release
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn sender(&self) -> &User {
+        &self.sender
+    }
+    pub fn set_notes_length(&mut self, notes_length: usize) -> &mut Self {
+        self.notes_length = Some(notes_length);
+        self
+    }
+}
+
+impl std::fmt::Display for GitHubReleaseEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let release = self.release();
+        write!(
+            f,
+            "🚀 <b>{sender}</b> {action} <a href=\"{url}\">{kind} {name}</a> <b>in {repository}</b>:\n\nTag: <code>{tag}</code>",
+            sender = self.sender(),
+            action = self.action(),
+            url = release.html_url(),
+            kind = if release.prerelease() {
+                "pre-release"
+            } else {
+                "release"
+            },
+            name = escape_html(release.display_name()),
+            repository = self.repository(),
+            tag = escape_html(release.tag_name()),
+        )?;
+        let notes = release.body().as_deref().unwrap_or_default().trim();
+        if !notes.is_empty() {
+            write!(
+                f,
+                "\n\n{}",
+                markdown_to_telegram_html(&truncate(notes, self.notes_length))
+            )?;
+        }
+        if !release.assets().is_empty() {
+            write!(
+                f,
+                "\n\nAssets:\n{}",
+                release
+                    .assets()
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl DisplayableEvent for GitHubReleaseEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        self.release().target_commitish().to_string()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Release
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.release.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Release {
+    html_url: String,
+    tag_name: String,
+    target_commitish: String,
+    name: Option<String>,
+    body: Option<String>,
+    prerelease: bool,
+    #[serde(default)]
+    assets: Vec<ReleaseAsset>,
+}
+
+impl Release {
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+    pub fn tag_name(&self) -> &str {
+        &self.tag_name
+    }
+    pub fn target_commitish(&self) -> &str {
+        &self.target_commitish
+    }
+    pub fn name(&self) -> &Option<String> {
+        &self.name
+    }
+    pub fn body(&self) -> &Option<String> {
+        &self.body
+    }
+    pub fn prerelease(&self) -> bool {
+        self.prerelease
+    }
+    pub fn assets(&self) -> &Vec<ReleaseAsset> {
+        &self.assets
+    }
+
+    /// Release name, fallback to tag name if name is blank.
+    pub fn display_name(&self) -> &str {
+        match self.name() {
+            Some(name) if !name.is_empty() => name,
+            _ => self.tag_name(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ReleaseAsset {
+    name: String,
+    size: u64,
+    browser_download_url: String,
+}
+
+impl ReleaseAsset {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+    pub fn browser_download_url(&self) -> &str {
+        &self.browser_download_url
+    }
+}
+
+impl std::fmt::Display for ReleaseAsset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "• <a href=\"{url}\">{name}</a> ({size:.1} KiB)",
+            url = self.browser_download_url(),
+            name = escape_html(self.name()),
+            size = self.size() as f64 / 1024.0,
+        )
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubDiscussionEvent {
+    action: String,
+    discussion: Discussion,
+    answer: Option<Comment>,
+    #[serde(default)]
+    changes: serde_json::Value,
+    repository: Repository,
+    sender: User,
+}
+
+impl GitHubDiscussionEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn discussion(&self) -> &Discussion {
+        &self.discussion
+    }
+    pub fn answer(&self) -> &Option<Comment> {
+        &self.answer
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn sender(&self) -> &User {
+        &self.sender
+    }
+
+    /// Previous category name, only exists in `category_changed` action.
+    pub fn category_changed_from(&self) -> Option<&str> {
+        self.changes["category"]["from"]["name"].as_str()
+    }
+}
+
+impl std::fmt::Display for GitHubDiscussionEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let discussion = self.discussion();
+        let (emoji, verb) = match self.action() {
+            "created" => ("🗣", "started"),
+            "answered" => ("✅", "marked an answer in"),
+            "category_changed" => ("🗂", "moved"),
+            action => ("🗣", action),
+        };
+        write!(
+            f,
+            "{emoji} <b>{sender}</b> {verb} <a href=\"{url}\">discussion #{number}</a> <b>in {repository}</b>:\n\n\
+            <b>{title}</b>\nCategory: {category}",
+            emoji = emoji,
+            sender = self.sender(),
+            verb = verb,
+            url = discussion.html_url(),
+            number = discussion.number(),
+            repository = self.repository(),
+            title = escape_html(discussion.title()),
+            category = discussion.category(),
+        )?;
+        if let Some(from) = self.category_changed_from() {
+            write!(f, " (was {})", escape_html(from))?;
+        }
+        if let Some(answer) = self.answer() {
+            write!(
+                f,
+                "\n<a href=\"{url}\">Answer</a> by <b>{author}</b>",
+                url = answer.html_url(),
+                author = answer.user(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl DisplayableEvent for GitHubDiscussionEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        String::new()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Discussion
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.discussion.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubDiscussionCommentEvent {
+    action: String,
+    comment: Comment,
+    discussion: Discussion,
+    repository: Repository,
+    sender: User,
+    #[serde(skip)]
+    comment_length: Option<usize>,
+}
+
+impl GitHubDiscussionCommentEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn comment(&self) -> &Comment {
+        &self.comment
+    }
+    pub fn discussion(&self) -> &Discussion {
+        &self.discussion
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn set_comment_length(&mut self, comment_length: usize) -> &mut Self {
+        self.comment_length = Some(comment_length);
+        self
+    }
+}
+
+impl std::fmt::Display for GitHubDiscussionCommentEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let discussion = self.discussion();
+        write!(
+            f,
+            "💬 <b>{author}</b> commented on <a href=\"{url}\">discussion #{number}</a> <b>in {repository}</b>:\n\n\
+            <b>{title}</b>\nCategory: {category}\n\n{body}",
+            author = self.comment().user(),
+            url = self.comment().html_url(),
+            number = discussion.number(),
+            repository = self.repository(),
+            title = escape_html(discussion.title()),
+            category = discussion.category(),
+            body = escape_html(&truncate(self.comment().body(), self.comment_length)),
+        )
+    }
+}
+
+impl DisplayableEvent for GitHubDiscussionCommentEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        String::new()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Comment
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.comment.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Discussion {
+    html_url: String,
+    number: u64,
+    title: String,
+    category: DiscussionCategory,
+}
+
+impl Discussion {
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+    pub fn category(&self) -> &DiscussionCategory {
+        &self.category
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DiscussionCategory {
+    name: String,
+}
+
+impl DiscussionCategory {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl std::fmt::Display for DiscussionCategory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<b>{}</b>", escape_html(self.name()))
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubDeploymentEvent {
+    action: String,
+    deployment: Deployment,
+    repository: Repository,
+}
+
+impl GitHubDeploymentEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn deployment(&self) -> &Deployment {
+        &self.deployment
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+}
+
+impl std::fmt::Display for GitHubDeploymentEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let deployment = self.deployment();
+        write!(
+            f,
+            "🚚 <b>{creator}</b> {action} <a href=\"{url}/deployments\">deployment</a> of <code>{sha}</code> (<code>{git_ref}</code>) to <b>{environment}</b> <b>in {repository}</b>",
+            creator = deployment.creator(),
+            action = self.action(),
+            url = self.repository().html_url(),
+            sha = short_hash(deployment.sha()),
+            git_ref = escape_html(deployment.git_ref()),
+            environment = escape_html(deployment.environment()),
+            repository = self.repository(),
+        )?;
+        if let Some(description) = deployment.description() {
+            if !description.is_empty() {
+                write!(f, "\n\n{}", escape_html(description))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DisplayableEvent for GitHubDeploymentEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        self.deployment().git_ref().to_string()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Deployment
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(format!("{}/deployments", self.repository.html_url))
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.deployment.creator.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubDeploymentStatusEvent {
+    deployment_status: DeploymentStatus,
+    deployment: Deployment,
+    repository: Repository,
+}
+
+impl GitHubDeploymentStatusEvent {
+    pub fn deployment_status(&self) -> &DeploymentStatus {
+        &self.deployment_status
+    }
+    pub fn deployment(&self) -> &Deployment {
+        &self.deployment
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+}
+
+impl std::fmt::Display for GitHubDeploymentStatusEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let deployment = self.deployment();
+        let status = self.deployment_status();
+        let emoji = match status.state() {
+            "success" => "✅",
+            "failure" | "error" => "❌",
+            "inactive" => "💤",
+            _ => "⏳",
+        };
+        write!(
+            f,
+            "{emoji} Deploy of <code>{sha}</code> (<code>{git_ref}</code>) to <b>{environment}</b>: <b>{state}</b> <b>in {repository}</b>\n\n\
+            Creator: <b>{creator}</b>",
+            emoji = emoji,
+            sha = short_hash(deployment.sha()),
+            git_ref = escape_html(deployment.git_ref()),
+            environment = escape_html(deployment.environment()),
+            state = status.state(),
+            repository = self.repository(),
+            creator = deployment.creator(),
+        )?;
+        if let Some(url) = status.target() {
+            write!(f, "\nTarget: <a href=\"{url}\">{url}</a>", url = url)?;
+        }
+        if let Some(url) = status.log_url() {
+            write!(f, "\n<a href=\"{}\">View logs</a>", url)?;
+        }
+        if let Some(description) = status.description() {
+            if !description.is_empty() {
+                write!(f, "\n\n{}", escape_html(description))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DisplayableEvent for GitHubDeploymentStatusEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        self.deployment().git_ref().to_string()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Deployment
+    }
+
+    fn event_url(&self) -> Option<String> {
+        self.deployment_status
+            .target()
+            .or_else(|| self.deployment_status.log_url())
+            .map(str::to_string)
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.deployment.creator.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Deployment {
+    sha: String,
+    #[serde(rename = "ref")]
+    git_ref: String,
+    environment: String,
+    description: Option<String>,
+    creator: User,
+}
+
+impl Deployment {
+    pub fn sha(&self) -> &str {
+        &self.sha
+    }
+    pub fn git_ref(&self) -> &str {
+        &self.git_ref
+    }
+    pub fn environment(&self) -> &str {
+        &self.environment
+    }
+    pub fn description(&self) -> &Option<String> {
+        &self.description
+    }
+    pub fn creator(&self) -> &User {
+        &self.creator
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DeploymentStatus {
+    state: String,
+    description: Option<String>,
+    environment_url: Option<String>,
+    target_url: Option<String>,
+    log_url: Option<String>,
+}
+
+impl DeploymentStatus {
+    pub fn state(&self) -> &str {
+        &self.state
+    }
+    pub fn description(&self) -> &Option<String> {
+        &self.description
+    }
+
+    /// Environment URL, fallback to the deprecated `target_url`.
+    pub fn target(&self) -> Option<&str> {
+        self.environment_url
+            .as_deref()
+            .or(self.target_url.as_deref())
+            .filter(|x| !x.is_empty())
+    }
+
+    pub fn log_url(&self) -> Option<&str> {
+        self.log_url.as_deref().filter(|x| !x.is_empty())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Issue {
+    html_url: String,
+    number: u64,
+    title: String,
+    // Only exists if this issue is a pull request
+    pull_request: Option<serde_json::Value>,
+}
+
+impl Issue {
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+    pub fn is_pull_request(&self) -> bool {
+        self.pull_request.is_some()
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Label {
+    name: String,
+}
+
+impl Label {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Comment {
+    html_url: String,
+    body: String,
+    user: User,
+}
+
+impl Comment {
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+}
+
+/// Common interface of `workflow_run`, `workflow_job` and `check_suite` events.
+pub trait CheckEvent: DisplayableEvent {
+    fn action(&self) -> &str;
+
+    fn conclusion(&self) -> Option<&str>;
+
+    /// Key used to remember previous conclusion, unique per repository, workflow and branch.
+    fn state_key(&self) -> String;
+}
+
+impl<F: ?Sized + Send + Sync> CheckEvent for Box<F>
+where
+    F: CheckEvent,
+{
+    fn action(&self) -> &str {
+        (**self).action()
+    }
+
+    fn conclusion(&self) -> Option<&str> {
+        (**self).conclusion()
+    }
+
+    fn state_key(&self) -> String {
+        (**self).state_key()
+    }
+}
+
+pub fn check_kind<E: CheckEvent + ?Sized>(event: &E) -> EventKind {
+    if is_failure_conclusion(event.conclusion().unwrap_or_default()) {
+        EventKind::CheckFailure
+    } else {
+        EventKind::Check
+    }
+}
+
+pub fn is_failure_conclusion(conclusion: &str) -> bool {
+    matches!(
+        conclusion,
+        "failure" | "cancelled" | "timed_out" | "startup_failure"
+    )
+}
+
+fn conclusion_emoji(conclusion: &str) -> &'static str {
+    match conclusion {
+        "success" => "✅",
+        "failure" | "startup_failure" | "error" => "❌",
+        "cancelled" => "⏹",
+        "timed_out" => "⏱",
+        _ => "⚪",
+    }
+}
+
+pub struct CheckSummary<'a> {
+    pub title: String,
+    pub url: &'a str,
+    pub conclusion: &'a str,
+    pub repository: &'a str,
+    pub branch: &'a str,
+    pub head_sha: &'a str,
+    pub commit_url: String,
+    pub commit_message: Option<&'a str>,
+    pub actor: &'a str,
+}
+
+impl<'a> std::fmt::Display for CheckSummary<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{emoji} <a href=\"{url}\">{title}</a> {conclusion} <b>on {repository}:{branch}</b>\n\n\
+            Commit <a href=\"{commit_url}\"><code>{short_sha}</code></a>",
+            emoji = conclusion_emoji(self.conclusion),
+            url = self.url,
+            title = self.title,
+            conclusion = self.conclusion.replace('_', " "),
+            repository = self.repository,
+            branch = self.branch,
+            commit_url = self.commit_url,
+            short_sha = short_hash(self.head_sha),
+        )?;
+        if let Some(message) = self.commit_message {
+            let title = message.split_once('\n').map_or(message, |(x, _)| x);
+            write!(f, ": {}", escape_html(title))?;
+        }
+        write!(f, "\nTriggered by <b>{}</b>", self.actor)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubWorkflowRunEvent {
+    action: String,
+    workflow_run: WorkflowRun,
+    repository: Repository,
+    sender: User,
+}
+
+impl GitHubWorkflowRunEvent {
+    pub fn workflow_run(&self) -> &WorkflowRun {
+        &self.workflow_run
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn sender(&self) -> &User {
+        &self.sender
+    }
+}
+
+impl std::fmt::Display for GitHubWorkflowRunEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let run = self.workflow_run();
+        CheckSummary {
+            title: format!("Workflow {} #{}", escape_html(run.name()), run.run_number()),
+            url: run.html_url(),
+            conclusion: run.conclusion().as_deref().unwrap_or_default(),
+            repository: self.repository().full_name(),
+            branch: run.head_branch().as_deref().unwrap_or_default(),
+            head_sha: run.head_sha(),
+            commit_url: format!("{}/commit/{}", self.repository().html_url(), run.head_sha()),
+            commit_message: run.head_commit().as_ref().map(|x| x.message()),
+            actor: run.actor().as_ref().unwrap_or(self.sender()).login(),
+        }
+        .fmt(f)
+    }
+}
+
+impl DisplayableEvent for GitHubWorkflowRunEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        self.workflow_run()
+            .head_branch()
+            .clone()
+            .unwrap_or_default()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        check_kind(self)
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.workflow_run.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(
+            self.workflow_run
+                .actor
+                .as_ref()
+                .unwrap_or(&self.sender)
+                .login(),
+        )
+    }
+}
+
+impl CheckEvent for GitHubWorkflowRunEvent {
+    fn action(&self) -> &str {
+        &self.action
+    }
+
+    fn conclusion(&self) -> Option<&str> {
+        self.workflow_run().conclusion().as_deref()
+    }
+
+    fn state_key(&self) -> String {
+        format!(
+            "{}/workflow/{}/{}",
+            self.get_full_name(),
+            self.workflow_run().name(),
+            self.branch_name()
+        )
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WorkflowRun {
+    name: String,
+    html_url: String,
+    run_number: u64,
+    head_branch: Option<String>,
+    head_sha: String,
+    conclusion: Option<String>,
+    head_commit: Option<HeadCommit>,
+    actor: Option<User>,
+}
+
+impl WorkflowRun {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+    pub fn run_number(&self) -> u64 {
+        self.run_number
+    }
+    pub fn head_branch(&self) -> &Option<String> {
+        &self.head_branch
+    }
+    pub fn head_sha(&self) -> &str {
+        &self.head_sha
+    }
+    pub fn conclusion(&self) -> &Option<String> {
+        &self.conclusion
+    }
+    pub fn head_commit(&self) -> &Option<HeadCommit> {
+        &self.head_commit
+    }
+    pub fn actor(&self) -> &Option<User> {
+        &self.actor
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubWorkflowJobEvent {
+    action: String,
+    workflow_job: WorkflowJob,
+    repository: Repository,
+    sender: User,
+}
+
+impl GitHubWorkflowJobEvent {
+    pub fn workflow_job(&self) -> &WorkflowJob {
+        &self.workflow_job
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn sender(&self) -> &User {
+        &self.sender
+    }
+}
+
+impl std::fmt::Display for GitHubWorkflowJobEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let job = self.workflow_job();
+        CheckSummary {
+            title: match job.workflow_name() {
+                Some(workflow) => format!(
+                    "Job {} of workflow {}",
+                    escape_html(job.name()),
+                    escape_html(workflow)
+                ),
+                None => format!("Job {}", escape_html(job.name())),
+            },
+            url: job.html_url(),
+            conclusion: job.conclusion().as_deref().unwrap_or_default(),
+            repository: self.repository().full_name(),
+            branch: job.head_branch().as_deref().unwrap_or_default(),
+            head_sha: job.head_sha(),
+            commit_url: format!("{}/commit/{}", self.repository().html_url(), job.head_sha()),
+            commit_message: None,
+            actor: self.sender().login(),
+        }
+        .fmt(f)
+    }
+}
+
+impl DisplayableEvent for GitHubWorkflowJobEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        self.workflow_job()
+            .head_branch()
+            .clone()
+            .unwrap_or_default()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        check_kind(self)
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.workflow_job.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+impl CheckEvent for GitHubWorkflowJobEvent {
+    fn action(&self) -> &str {
+        &self.action
+    }
+
+    fn conclusion(&self) -> Option<&str> {
+        self.workflow_job().conclusion().as_deref()
+    }
+
+    fn state_key(&self) -> String {
+        format!(
+            "{}/job/{}/{}/{}",
+            self.get_full_name(),
+            self.workflow_job()
+                .workflow_name()
+                .as_deref()
+                .unwrap_or_default(),
+            self.workflow_job().name(),
+            self.branch_name()
+        )
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WorkflowJob {
+    name: String,
+    workflow_name: Option<String>,
+    html_url: String,
+    head_branch: Option<String>,
+    head_sha: String,
+    conclusion: Option<String>,
+}
+
+impl WorkflowJob {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn workflow_name(&self) -> &Option<String> {
+        &self.workflow_name
+    }
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+    pub fn head_branch(&self) -> &Option<String> {
+        &self.head_branch
+    }
+    pub fn head_sha(&self) -> &str {
+        &self.head_sha
+    }
+    pub fn conclusion(&self) -> &Option<String> {
+        &self.conclusion
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubCheckSuiteEvent {
+    action: String,
+    check_suite: CheckSuite,
+    repository: Repository,
+    sender: User,
+}
+
+impl GitHubCheckSuiteEvent {
+    pub fn check_suite(&self) -> &CheckSuite {
+        &self.check_suite
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn sender(&self) -> &User {
+        &self.sender
+    }
+}
+
+impl std::fmt::Display for GitHubCheckSuiteEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let suite = self.check_suite();
+        CheckSummary {
+            title: format!("Check suite {}", escape_html(suite.app().name())),
+            url: &format!(
+                "{}/commit/{}/checks",
+                self.repository().html_url(),
+                suite.head_sha()
+            ),
+            conclusion: suite.conclusion().as_deref().unwrap_or_default(),
+            repository: self.repository().full_name(),
+            branch: suite.head_branch().as_deref().unwrap_or_default(),
+            head_sha: suite.head_sha(),
+            commit_url: format!(
+                "{}/commit/{}",
+                self.repository().html_url(),
+                suite.head_sha()
+            ),
+            commit_message: suite.head_commit().as_ref().map(|x| x.message()),
+            actor: self.sender().login(),
+        }
+        .fmt(f)
+    }
+}
+
+impl DisplayableEvent for GitHubCheckSuiteEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        self.check_suite().head_branch().clone().unwrap_or_default()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        check_kind(self)
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(format!(
+            "{}/commit/{}/checks",
+            self.repository.html_url, self.check_suite.head_sha
+        ))
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+impl CheckEvent for GitHubCheckSuiteEvent {
+    fn action(&self) -> &str {
+        &self.action
+    }
+
+    fn conclusion(&self) -> Option<&str> {
+        self.check_suite().conclusion().as_deref()
+    }
+
+    fn state_key(&self) -> String {
+        format!(
+            "{}/check_suite/{}/{}",
+            self.get_full_name(),
+            self.check_suite().app().name(),
+            self.branch_name()
+        )
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CheckSuite {
+    head_branch: Option<String>,
+    head_sha: String,
+    conclusion: Option<String>,
+    app: App,
+    head_commit: Option<HeadCommit>,
+}
+
+impl CheckSuite {
+    pub fn head_branch(&self) -> &Option<String> {
+        &self.head_branch
+    }
+    pub fn head_sha(&self) -> &str {
+        &self.head_sha
+    }
+    pub fn conclusion(&self) -> &Option<String> {
+        &self.conclusion
+    }
+    pub fn app(&self) -> &App {
+        &self.app
+    }
+    pub fn head_commit(&self) -> &Option<HeadCommit> {
+        &self.head_commit
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct App {
+    name: String,
+}
+
+impl App {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct HeadCommit {
+    message: String,
+}
+
+impl HeadCommit {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+fn severity_emoji(severity: &str) -> &'static str {
+    match severity.to_lowercase().as_str() {
+        "critical" => "🟥",
+        "high" => "🟧",
+        "medium" | "moderate" => "🟨",
+        _ => "🟦",
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubDependabotAlertEvent {
+    action: String,
+    alert: DependabotAlert,
+    repository: Repository,
+}
+
+impl GitHubDependabotAlertEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn alert(&self) -> &DependabotAlert {
+        &self.alert
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+}
+
+impl std::fmt::Display for GitHubDependabotAlertEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let alert = self.alert();
+        let advisory = alert.security_advisory();
+        let vulnerability = alert.security_vulnerability();
+        write!(
+            f,
+            "🚨 <a href=\"{url}\">Dependabot alert #{number}</a> {action} <b>in {repository}</b>:\n\n\
+            <b>{summary}</b>\n\
+            Severity: {emoji} <b>{severity}</b> (<code>{ghsa_id}</code>)\n\
+            Package: <code>{ecosystem}/{package}</code> <code>{range}</code>\n\
+            Manifest: <code>{manifest}</code>",
+            url = alert.html_url(),
+            number = alert.number(),
+            action = self.action().replace('_', " "),
+            repository = self.repository(),
+            summary = escape_html(advisory.summary()),
+            emoji = severity_emoji(advisory.severity()),
+            severity = advisory.severity(),
+            ghsa_id = advisory.ghsa_id(),
+            ecosystem = alert.dependency().package().ecosystem(),
+            package = escape_html(alert.dependency().package().name()),
+            range = escape_html(vulnerability.vulnerable_version_range()),
+            manifest = escape_html(alert.dependency().manifest_path()),
+        )?;
+        if let Some(version) = vulnerability.first_patched_version() {
+            write!(
+                f,
+                "\nPatched in: <code>{}</code>",
+                escape_html(version.identifier())
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl DisplayableEvent for GitHubDependabotAlertEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        String::new()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Security
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.alert.html_url.clone())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DependabotAlert {
+    number: u64,
+    html_url: String,
+    dependency: Dependency,
+    security_advisory: SecurityAdvisory,
+    security_vulnerability: SecurityVulnerability,
+}
+
+impl DependabotAlert {
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+    pub fn dependency(&self) -> &Dependency {
+        &self.dependency
+    }
+    pub fn security_advisory(&self) -> &SecurityAdvisory {
+        &self.security_advisory
+    }
+    pub fn security_vulnerability(&self) -> &SecurityVulnerability {
+        &self.security_vulnerability
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Dependency {
+    package: Package,
+    manifest_path: String,
+}
+
+impl Dependency {
+    pub fn package(&self) -> &Package {
+        &self.package
+    }
+    pub fn manifest_path(&self) -> &str {
+        &self.manifest_path
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Package {
+    ecosystem: String,
+    name: String,
+}
+
+impl Package {
+    pub fn ecosystem(&self) -> &str {
+        &self.ecosystem
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SecurityAdvisory {
+    ghsa_id: String,
+    summary: String,
+    severity: String,
+}
+
+impl SecurityAdvisory {
+    pub fn ghsa_id(&self) -> &str {
+        &self.ghsa_id
+    }
+    pub fn summary(&self) -> &str {
+        &self.summary
+    }
+    pub fn severity(&self) -> &str {
+        &self.severity
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SecurityVulnerability {
+    vulnerable_version_range: String,
+    first_patched_version: Option<PatchedVersion>,
+}
+
+impl SecurityVulnerability {
+    pub fn vulnerable_version_range(&self) -> &str {
+        &self.vulnerable_version_range
+    }
+    pub fn first_patched_version(&self) -> &Option<PatchedVersion> {
+        &self.first_patched_version
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct PatchedVersion {
+    identifier: String,
+}
+
+impl PatchedVersion {
+    pub fn identifier(&self) -> &str {
+        &self.identifier
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubCodeScanningAlertEvent {
+    action: String,
+    alert: CodeScanningAlert,
+    #[serde(rename = "ref")]
+    remote_ref: Option<String>,
+    repository: Repository,
+}
+
+impl GitHubCodeScanningAlertEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn alert(&self) -> &CodeScanningAlert {
+        &self.alert
+    }
+    pub fn remote_ref(&self) -> &Option<String> {
+        &self.remote_ref
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+}
+
+impl std::fmt::Display for GitHubCodeScanningAlertEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let alert = self.alert();
+        let rule = alert.rule();
+        let severity = rule.severity_level();
+        write!(
+            f,
+            "🚨 <a href=\"{url}\">Code scanning alert #{number}</a> {action} <b>in {repository}</b>:\n\n\
+            <b>{description}</b>\n\
+            Severity: {emoji} <b>{severity}</b>\n\
+            Rule: <code>{rule}</code> ({tool})",
+            url = alert.html_url(),
+            number = alert.number(),
+            action = self.action().replace('_', " "),
+            repository = self.repository(),
+            description = escape_html(rule.description()),
+            emoji = severity_emoji(severity),
+            severity = severity,
+            rule = escape_html(rule.id()),
+            tool = escape_html(alert.tool().name()),
+        )?;
+        if let Some(instance) = alert.most_recent_instance() {
+            write!(
+                f,
+                "\nLocation: <code>{}:{}</code>",
+                escape_html(instance.location().path()),
+                instance.location().start_line()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl DisplayableEvent for GitHubCodeScanningAlertEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        match self.remote_ref() {
+            Some(remote_ref) => remote_ref
+                .strip_prefix("refs/heads/")
+                .unwrap_or(remote_ref)
+                .to_string(),
+            None => String::new(),
+        }
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Security
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.alert.html_url.clone())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CodeScanningAlert {
+    number: u64,
+    html_url: String,
+    rule: CodeScanningRule,
+    tool: App,
+    most_recent_instance: Option<CodeScanningInstance>,
+}
+
+impl CodeScanningAlert {
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+    pub fn rule(&self) -> &CodeScanningRule {
+        &self.rule
+    }
+    pub fn tool(&self) -> &App {
+        &self.tool
+    }
+    pub fn most_recent_instance(&self) -> &Option<CodeScanningInstance> {
+        &self.most_recent_instance
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CodeScanningRule {
+    id: String,
+    description: String,
+    severity: Option<String>,
+    security_severity_level: Option<String>,
+}
+
+impl CodeScanningRule {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Security severity if present, otherwise the rule severity.
+    pub fn severity_level(&self) -> &str {
+        self.security_severity_level
+            .as_deref()
+            .or(self.severity.as_deref())
+            .unwrap_or("unknown")
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CodeScanningInstance {
+    location: CodeScanningLocation,
+}
+
+impl CodeScanningInstance {
+    pub fn location(&self) -> &CodeScanningLocation {
+        &self.location
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CodeScanningLocation {
+    path: String,
+    start_line: u64,
+}
+
+impl CodeScanningLocation {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    pub fn start_line(&self) -> u64 {
+        self.start_line
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubSecretScanningAlertEvent {
+    action: String,
+    alert: SecretScanningAlert,
+    repository: Repository,
+}
+
+impl GitHubSecretScanningAlertEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn alert(&self) -> &SecretScanningAlert {
+        &self.alert
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+}
+
+impl std::fmt::Display for GitHubSecretScanningAlertEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let alert = self.alert();
+        write!(
+            f,
+            "🚨 <a href=\"{url}\">Secret scanning alert #{number}</a> {action} <b>in {repository}</b>:\n\n\
+            Secret type: <b>{secret_type}</b>",
+            url = alert.html_url(),
+            number = alert.number(),
+            action = self.action(),
+            repository = self.repository(),
+            secret_type = escape_html(alert.display_secret_type()),
+        )?;
+        if let Some(resolution) = alert.resolution() {
+            write!(f, "\nResolution: <code>{}</code>", escape_html(resolution))?;
+        }
+        Ok(())
+    }
+}
+
+impl DisplayableEvent for GitHubSecretScanningAlertEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        String::new()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Security
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.alert.html_url.clone())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SecretScanningAlert {
+    number: u64,
+    html_url: String,
+    secret_type: String,
+    secret_type_display_name: Option<String>,
+    resolution: Option<String>,
+}
+
+impl SecretScanningAlert {
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+    pub fn resolution(&self) -> &Option<String> {
+        &self.resolution
+    }
+
+    pub fn display_secret_type(&self) -> &str {
+        self.secret_type_display_name
+            .as_deref()
+            .unwrap_or(&self.secret_type)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubRepositoryVulnerabilityAlertEvent {
+    action: String,
+    alert: RepositoryVulnerabilityAlert,
+    repository: Repository,
+}
+
+impl GitHubRepositoryVulnerabilityAlertEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn alert(&self) -> &RepositoryVulnerabilityAlert {
+        &self.alert
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+}
+
+impl std::fmt::Display for GitHubRepositoryVulnerabilityAlertEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let alert = self.alert();
+        write!(
+            f,
+            "🚨 <a href=\"{url}\">Vulnerability alert</a> {action} <b>in {repository}</b>:\n\n\
+            Severity: {emoji} <b>{severity}</b> (<code>{identifier}</code>)\n\
+            Package: <code>{package}</code> <code>{range}</code>",
+            url = alert.external_reference(),
+            action = self.action(),
+            repository = self.repository(),
+            emoji = severity_emoji(alert.severity()),
+            severity = alert.severity(),
+            identifier = escape_html(alert.external_identifier()),
+            package = escape_html(alert.affected_package_name()),
+            range = escape_html(alert.affected_range()),
+        )?;
+        if let Some(version) = alert.fixed_in() {
+            write!(f, "\nPatched in: <code>{}</code>", escape_html(version))?;
+        }
+        Ok(())
+    }
+}
+
+impl DisplayableEvent for GitHubRepositoryVulnerabilityAlertEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        String::new()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Security
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.alert.external_reference.clone())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RepositoryVulnerabilityAlert {
+    affected_package_name: String,
+    affected_range: String,
+    external_identifier: String,
+    external_reference: String,
+    severity: String,
+    fixed_in: Option<String>,
+}
+
+impl RepositoryVulnerabilityAlert {
+    pub fn affected_package_name(&self) -> &str {
+        &self.affected_package_name
+    }
+    pub fn affected_range(&self) -> &str {
+        &self.affected_range
+    }
+    pub fn external_identifier(&self) -> &str {
+        &self.external_identifier
+    }
+    pub fn external_reference(&self) -> &str {
+        &self.external_reference
+    }
+    pub fn severity(&self) -> &str {
+        &self.severity
+    }
+    pub fn fixed_in(&self) -> &Option<String> {
+        &self.fixed_in
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubStarEvent {
+    action: String,
+    repository: Repository,
+    sender: User,
+}
+
+impl GitHubStarEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn sender(&self) -> &User {
+        &self.sender
+    }
+}
+
+impl std::fmt::Display for GitHubStarEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (emoji, verb) = match self.action() {
+            "deleted" => ("💔", "unstarred"),
+            _ => ("⭐", "starred"),
+        };
+        write!(
+            f,
+            "{emoji} <a href=\"{sender_url}\">{sender}</a> {verb} <a href=\"{url}\">{repository}</a> ({count} stars in total)",
+            emoji = emoji,
+            sender_url = self.sender().html_url(),
+            sender = self.sender(),
+            verb = verb,
+            url = self.repository().html_url(),
+            repository = self.repository(),
+            count = self.repository().stargazers_count(),
+        )
+    }
+}
+
+impl DisplayableEvent for GitHubStarEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        String::new()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Star
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.repository.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubWatchEvent {
+    repository: Repository,
+    sender: User,
+}
+
+impl GitHubWatchEvent {
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn sender(&self) -> &User {
+        &self.sender
+    }
+}
+
+impl std::fmt::Display for GitHubWatchEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "👀 <a href=\"{sender_url}\">{sender}</a> started watching <a href=\"{url}\">{repository}</a> ({count} watchers in total)",
+            sender_url = self.sender().html_url(),
+            sender = self.sender(),
+            url = self.repository().html_url(),
+            repository = self.repository(),
+            count = self.repository().watchers_count(),
+        )
+    }
+}
+
+impl DisplayableEvent for GitHubWatchEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        String::new()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Star
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.repository.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubForkEvent {
+    forkee: Repository,
+    repository: Repository,
+    sender: User,
+}
+
+impl GitHubForkEvent {
+    pub fn forkee(&self) -> &Repository {
+        &self.forkee
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn sender(&self) -> &User {
+        &self.sender
+    }
+}
+
+impl std::fmt::Display for GitHubForkEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "🍴 <a href=\"{sender_url}\">{sender}</a> forked <b>{repository}</b> to <a href=\"{url}\">{forkee}</a> ({count} forks in total)",
+            sender_url = self.sender().html_url(),
+            sender = self.sender(),
+            repository = self.repository(),
+            url = self.forkee().html_url(),
+            forkee = self.forkee(),
+            count = self.repository().forks_count(),
+        )
+    }
+}
+
+impl DisplayableEvent for GitHubForkEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        String::new()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Fork
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.forkee.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubMemberEvent {
+    action: String,
+    member: User,
+    repository: Repository,
+    sender: User,
+}
+
+impl GitHubMemberEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn member(&self) -> &User {
+        &self.member
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn sender(&self) -> &User {
+        &self.sender
+    }
+}
+
+impl std::fmt::Display for GitHubMemberEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (emoji, verb, preposition) = match self.action() {
+            "added" => ("👥", "added", "to"),
+            "removed" => ("🚪", "removed", "from"),
+            _ => ("👥", "changed permission of", "in"),
+        };
+        write!(
+            f,
+            "{emoji} <b>{sender}</b> {verb} collaborator <a href=\"{member_url}\">{member}</a> {preposition} <b>{repository}</b>",
+            emoji = emoji,
+            sender = self.sender(),
+            verb = verb,
+            member_url = self.member().html_url(),
+            member = self.member(),
+            preposition = preposition,
+            repository = self.repository(),
+        )
+    }
+}
+
+impl DisplayableEvent for GitHubMemberEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        String::new()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Repository
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.repository.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubPublicEvent {
+    repository: Repository,
+    sender: User,
+}
+
+impl GitHubPublicEvent {
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn sender(&self) -> &User {
+        &self.sender
+    }
+}
+
+impl std::fmt::Display for GitHubPublicEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "🌍 <b>{sender}</b> made <a href=\"{url}\">{repository}</a> public",
+            sender = self.sender(),
+            url = self.repository().html_url(),
+            repository = self.repository(),
+        )
+    }
+}
+
+impl DisplayableEvent for GitHubPublicEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        String::new()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Repository
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.repository.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubRepositoryEvent {
+    action: String,
+    #[serde(default)]
+    changes: serde_json::Value,
+    repository: Repository,
+    sender: User,
+}
+
+impl GitHubRepositoryEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn sender(&self) -> &User {
+        &self.sender
+    }
+
+    /// Previous name of repository, only exists in `renamed` action.
+    pub fn renamed_from(&self) -> Option<&str> {
+        self.changes["repository"]["name"]["from"].as_str()
+    }
+}
+
+impl std::fmt::Display for GitHubRepositoryEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (emoji, verb) = match self.action() {
+            "created" => ("📦", "created"),
+            "deleted" => ("🗑", "deleted"),
+            "archived" => ("🗄", "archived"),
+            "unarchived" => ("📤", "unarchived"),
+            "renamed" => ("✏️", "renamed"),
+            "transferred" => ("🚚", "transferred"),
+            "publicized" => ("🌍", "made public"),
+            "privatized" => ("🔒", "made private"),
+            action => ("📦", action),
+        };
+        write!(
+            f,
+            "{emoji} <b>{sender}</b> {verb} repository <a href=\"{url}\">{repository}</a>",
+            emoji = emoji,
+            sender = self.sender(),
+            verb = verb,
+            url = self.repository().html_url(),
+            repository = self.repository(),
+        )?;
+        if let Some(name) = self.renamed_from() {
+            write!(f, " (was <code>{}</code>)", escape_html(name))?;
+        }
+        Ok(())
+    }
 }
 
-impl<F: ?Sized + Send + Sync> DisplayableEvent for Box<F>
-where
-    F: DisplayableEvent,
-{
+impl DisplayableEvent for GitHubRepositoryEvent {
     fn get_full_name(&self) -> &String {
-        (**self).get_full_name()
+        self.repository().full_name()
     }
 
     fn branch_name(&self) -> String {
-        (**self).branch_name()
+        String::new()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Repository
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.repository.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
     }
 }
 
 #[derive(Deserialize, Serialize, Debug)]
-pub struct GitHubEarlyParse {
+pub struct GitHubCommitCommentEvent {
+    action: String,
+    comment: CommitComment,
     repository: Repository,
+    #[serde(skip)]
+    comment_length: Option<usize>,
 }
 
-impl GitHubEarlyParse {
+impl GitHubCommitCommentEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn comment(&self) -> &CommitComment {
+        &self.comment
+    }
     pub fn repository(&self) -> &Repository {
         &self.repository
     }
+    pub fn set_comment_length(&mut self, comment_length: usize) -> &mut Self {
+        self.comment_length = Some(comment_length);
+        self
+    }
+}
+
+impl std::fmt::Display for GitHubCommitCommentEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let comment = self.comment();
+        write!(
+            f,
+            "💬 <b>{author}</b> commented on <a href=\"{url}\">commit {commit_id}</a> <b>in {repository}</b>:\n\n",
+            author = comment.user(),
+            url = comment.html_url(),
+            commit_id = short_hash(comment.commit_id()),
+            repository = self.repository(),
+        )?;
+        if let Some(location) = comment.location() {
+            write!(f, "<code>{}</code>\n\n", escape_html(&location))?;
+        }
+        write!(
+            f,
+            "{}",
+            escape_html(&truncate(comment.body(), self.comment_length))
+        )
+    }
+}
 
-    pub fn get_full_name(&self) -> &String {
+impl DisplayableEvent for GitHubCommitCommentEvent {
+    fn get_full_name(&self) -> &String {
         self.repository().full_name()
     }
+
+    fn branch_name(&self) -> String {
+        String::new()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Comment
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.comment.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.comment.user.login())
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug)]
-pub struct GitHubPingEvent {
-    zen: String,
+pub struct CommitComment {
+    html_url: String,
+    body: String,
+    user: User,
+    commit_id: String,
+    path: Option<String>,
+    line: Option<u64>,
 }
 
-impl GitHubPingEvent {
-    pub fn zen(&self) -> &str {
-        &self.zen
+impl CommitComment {
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+    pub fn commit_id(&self) -> &str {
+        &self.commit_id
+    }
+
+    /// `path:line` if this comment is attached to a line.
+    pub fn location(&self) -> Option<String> {
+        let path = self.path.as_ref()?;
+        Some(match self.line {
+            Some(line) => format!("{}:{}", path, line),
+            None => path.to_string(),
+        })
     }
 }
 
 #[derive(Deserialize, Serialize, Debug)]
-pub struct GitHubPushEvent {
-    #[serde(rename = "ref")]
-    remote_ref: String,
-    after: String,
-    before: String,
-    commits: Vec<Commit>,
-    compare: String,
+pub struct GitHubGollumEvent {
+    pages: Vec<WikiPage>,
     repository: Repository,
+    sender: User,
 }
 
-impl GitHubPushEvent {
-    pub fn remote_ref(&self) -> &str {
-        &self.remote_ref
+impl GitHubGollumEvent {
+    pub fn pages(&self) -> &Vec<WikiPage> {
+        &self.pages
     }
-    pub fn commits(&self) -> &Vec<Commit> {
-        &self.commits
+    pub fn repository(&self) -> &Repository {
+        &self.repository
     }
-    pub fn compare(&self) -> &str {
-        &self.compare
+    pub fn sender(&self) -> &User {
+        &self.sender
+    }
+}
+
+impl std::fmt::Display for GitHubGollumEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "📖 <b>{sender}</b> updated {count} wiki page{plural} <b>in {repository}</b>:\n\n{pages}",
+            sender = self.sender(),
+            count = self.pages().len(),
+            plural = if self.pages().len() == 1 { "" } else { "s" },
+            repository = self.repository(),
+            pages = self
+                .pages()
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>()
+                .join("\n"),
+        )
+    }
+}
+
+impl DisplayableEvent for GitHubGollumEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        String::new()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::Wiki
+    }
+
+    fn event_url(&self) -> Option<String> {
+        self.pages.first().map(|x| x.html_url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WikiPage {
+    title: String,
+    action: String,
+    html_url: String,
+}
+
+impl WikiPage {
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+}
+
+impl std::fmt::Display for WikiPage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "• {action} <a href=\"{url}\">{title}</a>",
+            action = self.action(),
+            url = self.html_url(),
+            title = escape_html(self.title()),
+        )
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubStatusEvent {
+    sha: String,
+    state: String,
+    context: String,
+    description: Option<String>,
+    target_url: Option<String>,
+    #[serde(default)]
+    branches: Vec<StatusBranch>,
+    commit: StatusCommit,
+    repository: Repository,
+    sender: User,
+}
+
+impl GitHubStatusEvent {
+    pub fn sha(&self) -> &str {
+        &self.sha
+    }
+    pub fn state(&self) -> &str {
+        &self.state
+    }
+    pub fn context(&self) -> &str {
+        &self.context
+    }
+    pub fn description(&self) -> &Option<String> {
+        &self.description
+    }
+    pub fn target_url(&self) -> &Option<String> {
+        &self.target_url
+    }
+    pub fn commit(&self) -> &StatusCommit {
+        &self.commit
     }
     pub fn repository(&self) -> &Repository {
         &self.repository
     }
-    pub fn after(&self) -> &str {
-        &self.after
+    pub fn sender(&self) -> &User {
+        &self.sender
     }
-    pub fn before(&self) -> &str {
-        &self.before
+
+    /// Target URL, fallback to the commit if it is missing or empty.
+    fn url(&self) -> &str {
+        match self.target_url() {
+            Some(url) if !url.is_empty() => url,
+            _ => self.commit().html_url(),
+        }
     }
 }
 
-impl std::fmt::Display for GitHubPushEvent {
+impl std::fmt::Display for GitHubStatusEvent {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let branch = self.remote_ref().rsplit_once('/').unwrap().1;
-        let git_ref = format!("{}:{}", self.repository(), branch);
-        if self.commits.len() == 1 {
-            let item = self.commits().index(0);
-            write!(
-                f,
-                "🔨 <a href=\"{url}\">{count} new commit</a> <b>to {git_ref}</b>:\n\n{commits}",
-                url = item.url(),
-                count = 1,
-                git_ref = git_ref,
-                commits = item
-            )
-        } else {
-            let l = self
-                .commits
-                .iter()
-                .map(|x| x.display(true))
-                .collect::<Vec<String>>()
-                .join("\n");
-            write!(
-                f,
-                "🔨 <a href=\"{url}\">{count} new commits</a> <b>to {git_ref}</b>:\n\n{commits}",
-                url = self.compare(),
-                count = self.commits.len(),
-                git_ref = git_ref,
-                commits = l,
-            )
+        let url = self.url();
+        let branch = self.branch_name();
+        CheckSummary {
+            title: format!("Status {}", escape_html(self.context())),
+            url,
+            conclusion: self.state(),
+            repository: self.repository().full_name(),
+            branch: &branch,
+            head_sha: self.sha(),
+            commit_url: format!("{}/commit/{}", self.repository().html_url(), self.sha()),
+            commit_message: Some(self.commit().commit().message()),
+            actor: self.sender().login(),
+        }
+        .fmt(f)?;
+        if let Some(description) = self.description() {
+            if !description.is_empty() {
+                write!(f, "\n\n{}", escape_html(description))?;
+            }
         }
+        Ok(())
     }
 }
 
-impl DisplayableEvent for GitHubPushEvent {
+impl DisplayableEvent for GitHubStatusEvent {
     fn get_full_name(&self) -> &String {
         self.repository().full_name()
     }
 
     fn branch_name(&self) -> String {
-        self.remote_ref().rsplit_once('/').unwrap().1.to_string()
+        self.branches
+            .first()
+            .map(|x| x.name().to_string())
+            .unwrap_or_default()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        check_kind(self)
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.url().to_string())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.sender.login())
+    }
+}
+
+impl CheckEvent for GitHubStatusEvent {
+    /// Status event has no action, treat every state except `pending` as completed.
+    fn action(&self) -> &str {
+        match self.state() {
+            "pending" => "pending",
+            _ => "completed",
+        }
+    }
+
+    fn conclusion(&self) -> Option<&str> {
+        match self.state() {
+            "error" => Some("failure"),
+            state => Some(state),
+        }
+    }
+
+    fn state_key(&self) -> String {
+        format!(
+            "{}/status/{}/{}",
+            self.get_full_name(),
+            self.context(),
+            self.branch_name()
+        )
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct StatusBranch {
+    name: String,
+}
+
+impl StatusBranch {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct StatusCommit {
+    html_url: String,
+    commit: HeadCommit,
+}
+
+impl StatusCommit {
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+    pub fn commit(&self) -> &HeadCommit {
+        &self.commit
     }
 }
 
+/// Fallback for events without a dedicated struct, only fields shared by most payloads are read.
 #[derive(Deserialize, Serialize, Debug)]
+pub struct GitHubGenericEvent {
+    #[serde(skip)]
+    event: String,
+    action: Option<String>,
+    repository: Repository,
+    sender: Option<User>,
+    #[serde(flatten)]
+    payload: serde_json::Map<String, serde_json::Value>,
+}
+
+impl GitHubGenericEvent {
+    pub fn event(&self) -> &str {
+        &self.event
+    }
+    pub fn action(&self) -> &Option<String> {
+        &self.action
+    }
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+    pub fn sender(&self) -> &Option<User> {
+        &self.sender
+    }
+    pub fn set_event(&mut self, event: &str) -> &mut Self {
+        self.event = event.to_string();
+        self
+    }
+
+    /// `html_url` of the object named after the event, e.g. `{"package": {"html_url": ...}}`,
+    /// otherwise of the repository.
+    pub fn html_url(&self) -> Option<&str> {
+        self.payload
+            .get(self.event())
+            .and_then(|x| x.get("html_url"))
+            .and_then(|x| x.as_str())
+            .filter(|x| !x.is_empty())
+            .or(Some(self.repository().html_url()))
+            .filter(|x| !x.is_empty())
+    }
+}
+
+impl std::fmt::Display for GitHubGenericEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "📨 <b>{}</b>", escape_html(self.event()))?;
+        if let Some(action) = self.action() {
+            write!(f, " ({})", escape_html(action))?;
+        }
+        write!(f, " <b>in {}</b>", self.repository())?;
+        if let Some(sender) = self.sender() {
+            write!(f, " by <b>{}</b>", sender)?;
+        }
+        if let Some(url) = self.html_url() {
+            write!(f, "\n\n<a href=\"{}\">View on GitHub</a>", escape_html(url))?;
+        }
+        Ok(())
+    }
+}
+
+impl DisplayableEvent for GitHubGenericEvent {
+    fn get_full_name(&self) -> &String {
+        self.repository().full_name()
+    }
+
+    fn branch_name(&self) -> String {
+        String::new()
+    }
+
+    fn event_url(&self) -> Option<String> {
+        self.html_url().map(str::to_string)
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        self.sender.as_ref().map(User::login)
+    }
+}
+
+pub fn check_0(s: &str) -> bool {
+    s.chars().all(|x| x == '0')
+}
+
+/// Cut `s` to at most `length` characters, `None` means no limit.
+pub fn truncate(s: &str, length: Option<usize>) -> String {
+    match length {
+        Some(length) if s.chars().count() > length => {
+            format!("{}…", s.chars().take(length).collect::<String>())
+        }
+        _ => s.to_string(),
+    }
+}
+
+/// First 8 characters of a commit hash, the whole string if it is shorter.
+pub fn short_hash(hash: &str) -> &str {
+    hash.char_indices().nth(8).map_or(hash, |(i, _)| &hash[..i])
+}
+
+pub fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Commit {
     id: String,
     message: String,
@@ -149,6 +3308,9 @@ pub struct Commit {
 }
 
 impl Commit {
+    pub fn new(id: String, message: String, url: String) -> Self {
+        Self { id, message, url }
+    }
     pub fn id(&self) -> &String {
         &self.id
     }
@@ -172,7 +3334,7 @@ impl Commit {
         format!(
             "<a href=\"{url}\">{commit_id}</a>: {content}",
             url = self.url(),
-            commit_id = &self.id()[..8],
+            commit_id = short_hash(self.id()),
             content = content
         )
     }
@@ -187,12 +3349,32 @@ impl std::fmt::Display for Commit {
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Repository {
     full_name: String,
+    #[serde(default)]
+    html_url: String,
+    #[serde(default, alias = "stars_count")]
+    stargazers_count: u64,
+    #[serde(default)]
+    watchers_count: u64,
+    #[serde(default)]
+    forks_count: u64,
 }
 
 impl Repository {
     pub fn full_name(&self) -> &String {
         &self.full_name
     }
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+    pub fn stargazers_count(&self) -> u64 {
+        self.stargazers_count
+    }
+    pub fn watchers_count(&self) -> u64 {
+        self.watchers_count
+    }
+    pub fn forks_count(&self) -> u64 {
+        self.forks_count
+    }
 }
 
 impl std::fmt::Display for Repository {
@@ -242,6 +3424,10 @@ impl Response {
     pub fn new_parse_error(e: serde_json::Error) -> Self {
         Self::reason(500, e.to_string())
     }
+    #[cfg(test)]
+    pub fn status(&self) -> u16 {
+        self.status
+    }
 }
 
 impl IntoResponse for Response {
@@ -292,20 +3478,80 @@ where
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct CommandBundle {
-    receiver: Vec<i64>,
+/// Sink independent copy of an event, rendered by each sink in its own format.
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    repository: String,
+    branch: String,
+    kind: EventKind,
+    /// Telegram HTML produced by `Display`
     text: String,
+    url: Option<String>,
+    actor: Option<String>,
+    commits: Vec<Commit>,
 }
 
-impl CommandBundle {
-    pub fn new(receiver: Vec<i64>, text: String) -> Self {
-        Self { receiver, text }
+impl Notification {
+    pub fn new<E: DisplayableEvent + ?Sized>(event: &E) -> Self {
+        Self {
+            repository: event.get_full_name().clone(),
+            branch: event.branch_name(),
+            kind: event.event_kind(),
+            text: event.to_string(),
+            url: event.event_url(),
+            actor: event.event_actor().map(str::to_string),
+            commits: event.event_commits().to_vec(),
+        }
     }
-    pub fn receiver(&self) -> &Vec<i64> {
-        &self.receiver
+    pub fn repository(&self) -> &str {
+        &self.repository
+    }
+    pub fn branch(&self) -> &str {
+        &self.branch
+    }
+    pub fn kind(&self) -> EventKind {
+        self.kind
     }
     pub fn text(&self) -> &str {
         &self.text
     }
+    pub fn url(&self) -> &Option<String> {
+        &self.url
+    }
+    pub fn actor(&self) -> &Option<String> {
+        &self.actor
+    }
+    pub fn commits(&self) -> &Vec<Commit> {
+        &self.commits
+    }
+
+    /// First line of the message without markup.
+    pub fn title(&self) -> String {
+        html_to_plain(self.text().lines().next().unwrap_or_default())
+    }
+}
+
+/// Receivers are chat IDs for Telegram, webhook URLs for Discord and so on.
+#[derive(Debug, Clone)]
+pub struct CommandBundle<T = i64> {
+    receiver: Vec<T>,
+    notification: Arc<Notification>,
+}
+
+impl<T> CommandBundle<T> {
+    pub fn new(receiver: Vec<T>, notification: Arc<Notification>) -> Self {
+        Self {
+            receiver,
+            notification,
+        }
+    }
+    pub fn receiver(&self) -> &Vec<T> {
+        &self.receiver
+    }
+    pub fn notification(&self) -> &Notification {
+        &self.notification
+    }
+    pub fn text(&self) -> &str {
+        self.notification.text()
+    }
 }
@@ -0,0 +1,562 @@
+/*
+ ** Copyright (C) 2021 KunoiSayami
+ **
+ ** This program is free software: you can redistribute it and/or modify
+ ** it under the terms of the GNU Affero General Public License as published by
+ ** the Free Software Foundation, either version 3 of the License, or
+ ** any later version.
+ **
+ ** This program is distributed in the hope that it will be useful,
+ ** but WITHOUT ANY WARRANTY; without even the implied warranty of
+ ** MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ ** GNU Affero General Public License for more details.
+ **
+ ** You should have received a copy of the GNU Affero General Public License
+ ** along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::configure::Repository as RepositoryConfig;
+use crate::datastructures::{
+    check_0, check_kind, escape_html, short_hash, CheckEvent, CheckSummary, Commit,
+    DisplayableEvent, EventKind, PushKind, Response,
+};
+use crate::provider::{header_str, is_push_enabled, NormalizedEvent, WebhookProvider};
+use axum::http::HeaderMap;
+use serde_derive::{Deserialize, Serialize};
+use std::fmt::Formatter;
+
+pub struct GitLabProvider;
+
+impl WebhookProvider for GitLabProvider {
+    fn event_type(&self, headers: &HeaderMap) -> Option<String> {
+        header_str(headers, "X-Gitlab-Event")
+    }
+
+    fn full_name(&self, _event_type: &str, body: &[u8]) -> serde_json::Result<String> {
+        Ok(serde_json::from_slice::<GitLabEarlyParse>(body)?
+            .get_full_name()
+            .clone())
+    }
+
+    /// GitLab sends the secret token as is instead of a signature.
+    fn verify_signature(
+        &self,
+        headers: &HeaderMap,
+        _body: &[u8],
+        secrets: &str,
+    ) -> Result<(), &'static str> {
+        match headers.get("X-Gitlab-Token") {
+            Some(val) if secrets.eq(val) => Ok(()),
+            Some(_) => Err("Token error"),
+            None => Err("Token header not found"),
+        }
+    }
+
+    fn parse(
+        &self,
+        event_type: &str,
+        body: &[u8],
+        settings: &RepositoryConfig,
+    ) -> Result<Vec<NormalizedEvent>, Response> {
+        match event_type {
+            "Push Hook" | "Tag Push Hook" => {
+                let event = match serde_json::from_slice::<GitLabPushEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                if !is_push_enabled(settings, event.kind(), event.created()) {
+                    return Err(Response::new_empty());
+                }
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "Merge Request Hook" => {
+                let event = match serde_json::from_slice::<GitLabMergeRequestEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                if !settings
+                    .pull_request_actions()
+                    .iter()
+                    .any(|x| x.eq(event.action_kind()))
+                {
+                    return Err(Response::reason(204, "Skipped."));
+                }
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "Pipeline Hook" => {
+                let event = match serde_json::from_slice::<GitLabPipelineEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                Ok(vec![NormalizedEvent::Check(Box::new(event))])
+            }
+            _ => Err(Response::reason(
+                400,
+                format!("Unsupported event type {:?}", event_type),
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitLabEarlyParse {
+    project: GitLabProject,
+}
+
+impl GitLabEarlyParse {
+    pub fn project(&self) -> &GitLabProject {
+        &self.project
+    }
+
+    pub fn get_full_name(&self) -> &String {
+        self.project().path_with_namespace()
+    }
+}
+
+/// Payload of both `Push Hook` and `Tag Push Hook`.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitLabPushEvent {
+    #[serde(rename = "ref")]
+    remote_ref: String,
+    before: String,
+    after: String,
+    user_username: String,
+    commits: Vec<Commit>,
+    total_commits_count: usize,
+    project: GitLabProject,
+}
+
+impl GitLabPushEvent {
+    pub fn remote_ref(&self) -> &str {
+        &self.remote_ref
+    }
+    pub fn before(&self) -> &str {
+        &self.before
+    }
+    pub fn after(&self) -> &str {
+        &self.after
+    }
+    pub fn user_username(&self) -> &str {
+        &self.user_username
+    }
+    pub fn commits(&self) -> &Vec<Commit> {
+        &self.commits
+    }
+    pub fn total_commits_count(&self) -> usize {
+        self.total_commits_count
+    }
+    pub fn project(&self) -> &GitLabProject {
+        &self.project
+    }
+
+    /// Tag name if this event is pushed to a tag.
+    pub fn tag_name(&self) -> Option<&str> {
+        self.remote_ref().strip_prefix("refs/tags/")
+    }
+
+    pub fn created(&self) -> bool {
+        check_0(self.before())
+    }
+    pub fn deleted(&self) -> bool {
+        check_0(self.after())
+    }
+
+    /// GitLab does not tell whether a push is forced, so `ForcePushed` is never returned.
+    pub fn kind(&self) -> PushKind {
+        if self.tag_name().is_some() {
+            PushKind::Tag
+        } else if self.deleted() {
+            PushKind::BranchDeleted
+        } else if self.created() {
+            PushKind::BranchCreated
+        } else {
+            PushKind::Commits
+        }
+    }
+
+    pub fn compare(&self) -> String {
+        format!(
+            "{}/-/compare/{}...{}",
+            self.project().web_url(),
+            self.before(),
+            self.after()
+        )
+    }
+
+    fn format_commits(&self) -> String {
+        self.commits()
+            .iter()
+            .map(|x| x.display(true))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl std::fmt::Display for GitLabPushEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let git_ref = format!("{}:{}", self.project(), self.branch_name());
+        match self.kind() {
+            PushKind::Tag => write!(
+                f,
+                "🏷 <b>{sender}</b> <a href=\"{url}/-/tags/{tag}\">tag {tag}</a> <b>pushed to {project}</b>",
+                sender = self.user_username(),
+                url = self.project().web_url(),
+                tag = escape_html(self.tag_name().unwrap_or_default()),
+                project = self.project(),
+            ),
+            PushKind::BranchCreated => {
+                write!(
+                    f,
+                    "🌱 <b>{sender}</b> <a href=\"{url}/-/tree/{branch}\">created branch</a> <b>{git_ref}</b>",
+                    sender = self.user_username(),
+                    url = self.project().web_url(),
+                    branch = self.branch_name(),
+                    git_ref = git_ref,
+                )?;
+                if !self.commits().is_empty() {
+                    write!(f, ":\n\n{}", self.format_commits())?;
+                }
+                Ok(())
+            }
+            PushKind::BranchDeleted => write!(
+                f,
+                "🗑 <b>{sender}</b> deleted branch <b>{git_ref}</b> (was <code>{before}</code>)",
+                sender = self.user_username(),
+                git_ref = git_ref,
+                before = short_hash(self.before()),
+            ),
+            PushKind::ForcePushed | PushKind::Commits => {
+                let count = self.total_commits_count();
+                let url = match self.commits().as_slice() {
+                    [commit] => commit.url().to_string(),
+                    _ => self.compare(),
+                };
+                write!(
+                    f,
+                    "🔨 <a href=\"{url}\">{count} new commit{plural}</a> <b>to {git_ref}</b>:\n\n{commits}",
+                    url = url,
+                    count = count,
+                    plural = if count == 1 { "" } else { "s" },
+                    git_ref = git_ref,
+                    commits = self.format_commits(),
+                )
+            }
+        }
+    }
+}
+
+impl DisplayableEvent for GitLabPushEvent {
+    fn get_full_name(&self) -> &String {
+        self.project().path_with_namespace()
+    }
+
+    fn branch_name(&self) -> String {
+        self.remote_ref()
+            .strip_prefix("refs/heads/")
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::from(self.kind())
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.compare())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.user_username())
+    }
+
+    fn event_commits(&self) -> &[Commit] {
+        &self.commits
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitLabMergeRequestEvent {
+    user: GitLabUser,
+    project: GitLabProject,
+    object_attributes: MergeRequestAttributes,
+}
+
+impl GitLabMergeRequestEvent {
+    pub fn user(&self) -> &GitLabUser {
+        &self.user
+    }
+    pub fn project(&self) -> &GitLabProject {
+        &self.project
+    }
+    pub fn object_attributes(&self) -> &MergeRequestAttributes {
+        &self.object_attributes
+    }
+
+    /// Action translated to the names used by `pull_request_actions`, e.g. `open` to `opened`.
+    pub fn action_kind(&self) -> &str {
+        match self.object_attributes().action().as_deref() {
+            Some("open") => "opened",
+            Some("close") => "closed",
+            Some("reopen") => "reopened",
+            Some("merge") => "merged",
+            Some(action) => action,
+            None => "",
+        }
+    }
+}
+
+impl std::fmt::Display for GitLabMergeRequestEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mr = self.object_attributes();
+        let (emoji, verb) = match self.action_kind() {
+            "opened" => ("🔀", "opened"),
+            "merged" => ("🟣", "merged"),
+            "closed" => ("🚫", "closed"),
+            "reopened" => ("🔁", "reopened"),
+            action => ("🔀", action),
+        };
+        write!(
+            f,
+            "{emoji} <b>{sender}</b> {verb} <a href=\"{url}\">merge request !{iid}</a> <b>in {project}</b>:\n\n\
+            <b>{title}</b>\n\
+            <code>{source}</code> → <code>{target}</code>",
+            emoji = emoji,
+            sender = self.user(),
+            verb = verb,
+            url = mr.url(),
+            iid = mr.iid(),
+            project = self.project(),
+            title = escape_html(mr.title()),
+            source = mr.source_branch(),
+            target = mr.target_branch(),
+        )
+    }
+}
+
+impl DisplayableEvent for GitLabMergeRequestEvent {
+    fn get_full_name(&self) -> &String {
+        self.project().path_with_namespace()
+    }
+
+    fn branch_name(&self) -> String {
+        self.object_attributes().target_branch().to_string()
+    }
+
+    fn event_kind(&self) -> EventKind {
+        EventKind::PullRequest
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.object_attributes.url.clone())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.user.username())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct MergeRequestAttributes {
+    iid: u64,
+    title: String,
+    url: String,
+    action: Option<String>,
+    source_branch: String,
+    target_branch: String,
+}
+
+impl MergeRequestAttributes {
+    pub fn iid(&self) -> u64 {
+        self.iid
+    }
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+    pub fn action(&self) -> &Option<String> {
+        &self.action
+    }
+    pub fn source_branch(&self) -> &str {
+        &self.source_branch
+    }
+    pub fn target_branch(&self) -> &str {
+        &self.target_branch
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitLabPipelineEvent {
+    object_attributes: PipelineAttributes,
+    user: GitLabUser,
+    project: GitLabProject,
+    commit: Option<Commit>,
+}
+
+impl GitLabPipelineEvent {
+    pub fn object_attributes(&self) -> &PipelineAttributes {
+        &self.object_attributes
+    }
+    pub fn user(&self) -> &GitLabUser {
+        &self.user
+    }
+    pub fn project(&self) -> &GitLabProject {
+        &self.project
+    }
+    pub fn commit(&self) -> &Option<Commit> {
+        &self.commit
+    }
+
+    /// Older GitLab does not include `url` in pipeline attributes.
+    pub fn url(&self) -> String {
+        match self.object_attributes().url() {
+            Some(url) => url.to_string(),
+            None => format!(
+                "{}/-/pipelines/{}",
+                self.project().web_url(),
+                self.object_attributes().id()
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for GitLabPipelineEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let pipeline = self.object_attributes();
+        CheckSummary {
+            title: format!("Pipeline #{}", pipeline.id()),
+            url: &self.url(),
+            conclusion: self.conclusion().unwrap_or_default(),
+            repository: self.project().path_with_namespace(),
+            branch: pipeline.remote_ref(),
+            head_sha: pipeline.sha(),
+            commit_url: format!("{}/-/commit/{}", self.project().web_url(), pipeline.sha()),
+            commit_message: self.commit().as_ref().map(|x| x.message()),
+            actor: self.user().username(),
+        }
+        .fmt(f)
+    }
+}
+
+impl DisplayableEvent for GitLabPipelineEvent {
+    fn get_full_name(&self) -> &String {
+        self.project().path_with_namespace()
+    }
+
+    fn branch_name(&self) -> String {
+        if self.object_attributes().tag() {
+            String::new()
+        } else {
+            self.object_attributes().remote_ref().to_string()
+        }
+    }
+
+    fn event_kind(&self) -> EventKind {
+        check_kind(self)
+    }
+
+    fn event_url(&self) -> Option<String> {
+        Some(self.url())
+    }
+
+    fn event_actor(&self) -> Option<&str> {
+        Some(self.user.username())
+    }
+}
+
+impl CheckEvent for GitLabPipelineEvent {
+    fn action(&self) -> &str {
+        match self.object_attributes().status() {
+            "success" | "failed" | "canceled" | "skipped" => "completed",
+            status => status,
+        }
+    }
+
+    /// Status translated to GitHub check conclusion.
+    fn conclusion(&self) -> Option<&str> {
+        Some(match self.object_attributes().status() {
+            "failed" => "failure",
+            "canceled" => "cancelled",
+            status => status,
+        })
+    }
+
+    fn state_key(&self) -> String {
+        format!(
+            "{}/pipeline/{}",
+            self.get_full_name(),
+            self.object_attributes().remote_ref()
+        )
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct PipelineAttributes {
+    id: u64,
+    #[serde(rename = "ref")]
+    remote_ref: String,
+    tag: bool,
+    sha: String,
+    status: String,
+    url: Option<String>,
+}
+
+impl PipelineAttributes {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    pub fn remote_ref(&self) -> &str {
+        &self.remote_ref
+    }
+    pub fn tag(&self) -> bool {
+        self.tag
+    }
+    pub fn sha(&self) -> &str {
+        &self.sha
+    }
+    pub fn status(&self) -> &str {
+        &self.status
+    }
+    pub fn url(&self) -> &Option<String> {
+        &self.url
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitLabProject {
+    path_with_namespace: String,
+    web_url: String,
+}
+
+impl GitLabProject {
+    pub fn path_with_namespace(&self) -> &String {
+        &self.path_with_namespace
+    }
+    pub fn web_url(&self) -> &str {
+        &self.web_url
+    }
+}
+
+impl std::fmt::Display for GitLabProject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path_with_namespace)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GitLabUser {
+    username: String,
+}
+
+impl GitLabUser {
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+}
+
+impl std::fmt::Display for GitLabUser {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.username)
+    }
+}
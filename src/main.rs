@@ -15,47 +15,200 @@
  ** along with this program. If not, see <https://www.gnu.org/licenses/>.
  */
 
-use crate::configure::Config;
+use crate::configure::{ActionsNotify, Config, Endpoint, Repository as RepositoryConfig, SinkKind};
 use crate::datastructures::{
-    AuthorizationGuard, CommandBundle, DisplayableEvent, GitHubEarlyParse, GitHubPingEvent,
-    GitHubPushEvent, Response,
+    is_failure_conclusion, AuthorizationGuard, CheckEvent, CommandBundle, DisplayableEvent,
+    Notification, Response,
 };
+use crate::provider::{detect_provider, provider_of, NormalizedEvent, WebhookProvider};
 use axum::body::{Body, HttpBody};
-use axum::http::{Request as HttpRequest, StatusCode};
+use axum::http::{HeaderMap, Request as HttpRequest, StatusCode};
 use axum::response::IntoResponse;
 use axum::{Extension, Router};
 use clap::arg;
-use hmac::{Hmac, Mac};
 use log::{debug, error, info, warn};
 use once_cell::sync::OnceCell;
-use sha2::Sha256;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Debug;
+use std::future::Future;
 use std::path::Path;
+use std::pin::Pin;
 use std::sync::Arc;
 use teloxide::prelude::{Request, Requester, RequesterExt};
 use teloxide::types::{ChatId, ParseMode};
 use teloxide::Bot;
 use tokio::sync::{mpsc, RwLock};
+use tokio::task::JoinHandle;
 use tower::ServiceBuilder;
 use tower_http::trace::TraceLayer;
 
 static AUTH_TOKEN: OnceCell<String> = OnceCell::new();
 
+mod bitbucket;
 mod configure;
 mod datastructures;
+mod gitlab;
+mod markdown;
+mod provider;
+mod sink;
 #[cfg(test)]
 mod test;
 
 const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+// Most workflows remembered as failed by `ActionsNotify::Changed`
+const CHECK_STATE_LIMIT: usize = 4096;
 
 #[derive(Debug)]
-enum Command {
+enum Command<T = i64> {
     Terminate,
-    Bundle(CommandBundle),
+    Bundle(CommandBundle<T>),
+}
+
+/// Senders of notifiers other than Telegram, only running sinks are present.
+#[derive(Default, Clone)]
+struct Sinks(HashMap<SinkKind, mpsc::Sender<Command<String>>>);
+
+impl Sinks {
+    fn insert(&mut self, kind: SinkKind, sender: mpsc::Sender<Command<String>>) {
+        self.0.insert(kind, sender);
+    }
+
+    /// Queue notification to receivers of every running sink set in `settings`.
+    fn send(&self, settings: &RepositoryConfig, notification: &Arc<Notification>) {
+        for (kind, sender) in &self.0 {
+            let receiver = settings.sink_send_to(*kind);
+            if !receiver.is_empty() {
+                try_send(kind.name(), sender, receiver, notification);
+            }
+        }
+    }
+
+    async fn terminate(&self) {
+        for sender in self.0.values() {
+            sender.send(Command::Terminate).await.ok();
+        }
+    }
+}
+
+/// Queue notification without waiting, it is dropped if the daemon is busy or exited,
+/// so a slow sink never holds up the request handler.
+fn try_send<T>(
+    name: &str,
+    sender: &mpsc::Sender<Command<T>>,
+    receiver: &[T],
+    notification: &Arc<Notification>,
+) where
+    T: Clone + Debug,
+{
+    let bundle = CommandBundle::new(receiver.to_vec(), notification.clone());
+    if let Err(e) = sender.try_send(Command::Bundle(bundle)) {
+        error!(
+            "{} daemon is busy or exited, dropped notification: {}",
+            name, e
+        );
+    }
+}
+
+/// Daemon of sink, `None` if it is not configured.
+fn sink_daemon(
+    config: &Config,
+    kind: SinkKind,
+    rx: mpsc::Receiver<Command<String>>,
+) -> Option<Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>> {
+    // Sinks without their own section are started if any repository sends to them
+    let used = config.is_sink_used(kind);
+    Some(match kind {
+        SinkKind::Discord if used => Box::pin(sink::discord::process_discord_message(rx)),
+        SinkKind::Slack if used => Box::pin(sink::slack::process_slack_message(rx)),
+        SinkKind::Teams if used => Box::pin(sink::teams::process_teams_message(
+            config.teams().clone(),
+            rx,
+        )),
+        SinkKind::Ntfy if used => {
+            Box::pin(sink::ntfy::process_ntfy_message(config.ntfy().clone(), rx))
+        }
+        SinkKind::Matrix => Box::pin(sink::matrix::process_matrix_message(
+            config.matrix().clone()?,
+            rx,
+        )),
+        SinkKind::Email => Box::pin(sink::email::process_email_message(
+            config.smtp().clone()?,
+            rx,
+        )),
+        SinkKind::Gotify => Box::pin(sink::gotify::process_gotify_message(
+            config.gotify().clone()?,
+            rx,
+        )),
+        SinkKind::Irc => Box::pin(sink::irc::process_irc_message(
+            config.irc().clone()?,
+            config.irc_channels(),
+            rx,
+        )),
+        SinkKind::Xmpp => Box::pin(sink::xmpp::process_xmpp_message(
+            config.xmpp().clone()?,
+            config.xmpp_addresses(),
+            rx,
+        )),
+        SinkKind::Webhook if !config.webhooks().is_empty() => Box::pin(
+            sink::webhook::process_webhook_message(config.webhooks().clone(), rx),
+        ),
+        _ => return None,
+    })
+}
+
+/// Spawn daemon of sink, the error is logged as soon as it exits instead of at shutdown.
+fn spawn_sink<F>(name: &'static str, daemon: F) -> JoinHandle<anyhow::Result<()>>
+where
+    F: Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let ret = daemon.await;
+        if let Err(ref e) = ret {
+            error!("{} sink daemon exited: {:?}", name, e);
+        }
+        ret
+    })
+}
+
+/// Keys from `CheckEvent::state_key` of which last completed run is failed,
+/// the oldest one is forgotten once `CHECK_STATE_LIMIT` is reached.
+#[derive(Default)]
+struct CheckState {
+    keys: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+impl CheckState {
+    /// Return `true` if `key` is not remembered before.
+    fn insert(&mut self, key: String) -> bool {
+        if self.keys.contains(&key) {
+            return false;
+        }
+        if self.order.len() >= CHECK_STATE_LIMIT {
+            if let Some(oldest) = self.order.pop_front() {
+                warn!("Too many failed workflows remembered, forget {}", oldest);
+                self.keys.remove(&oldest);
+            }
+        }
+        self.keys.insert(key.clone());
+        self.order.push_back(key);
+        true
+    }
+
+    /// Return `true` if `key` is remembered before.
+    fn remove(&mut self, key: &str) -> bool {
+        if !self.keys.remove(key) {
+            return false;
+        }
+        self.order.retain(|x| x != key);
+        true
+    }
 }
 
 struct ExtraData {
     bot_tx: mpsc::Sender<Command>,
+    sinks: Sinks,
+    check_state: CheckState,
 }
 
 async fn process_send_message(
@@ -97,12 +250,84 @@ async fn process_send_message(
     Ok(())
 }
 
-fn check_0(s: &str) -> bool {
-    s.chars().into_iter().all(|x| x == '0')
+async fn forward_event<E: DisplayableEvent>(
+    data: &Arc<RwLock<ExtraData>>,
+    settings: &RepositoryConfig,
+    event: &E,
+) -> Response {
+    forward_event_to(data, settings, settings.send_to(), true, event).await
+}
+
+/// Security events go to `security_send_to` only, sinks have no separate destination for them.
+async fn forward_security_event<E: DisplayableEvent>(
+    data: &Arc<RwLock<ExtraData>>,
+    settings: &RepositoryConfig,
+    event: &E,
+) -> Response {
+    forward_event_to(data, settings, settings.security_send_to(), false, event).await
+}
+
+async fn forward_event_to<E: DisplayableEvent>(
+    data: &Arc<RwLock<ExtraData>>,
+    settings: &RepositoryConfig,
+    receiver: &[i64],
+    to_sinks: bool,
+    event: &E,
+) -> Response {
+    if settings.branch_ignore().contains(&event.branch_name()) {
+        return Response::reason(204, "Skipped.");
+    }
+    debug!("Forward event from {}", event.get_full_name());
+    let notification = Arc::new(Notification::new(event));
+    // Senders are cloned so the lock is not held while sending
+    let (bot_tx, sinks) = {
+        let data = data.read().await;
+        (data.bot_tx.clone(), data.sinks.clone())
+    };
+    try_send("Telegram", &bot_tx, receiver, &notification);
+    if to_sinks {
+        sinks.send(settings, &notification);
+    }
+    Response::new_ok()
+}
+
+async fn forward_check_event<E: CheckEvent>(
+    data: &Arc<RwLock<ExtraData>>,
+    settings: &RepositoryConfig,
+    event: &E,
+) -> Response {
+    if event.action() != "completed" || settings.branch_ignore().contains(&event.branch_name()) {
+        return Response::reason(204, "Skipped.");
+    }
+    let conclusion = event.conclusion().unwrap_or_default();
+    let failed = is_failure_conclusion(conclusion);
+    let notify = match settings.actions_notify() {
+        ActionsNotify::Failure => failed,
+        ActionsNotify::Completed => true,
+        ActionsNotify::Changed => {
+            // Neutral conclusions (e.g. skipped) do not change state
+            if !failed && conclusion != "success" {
+                false
+            } else {
+                let key = event.state_key();
+                let mut data = data.write().await;
+                if !failed {
+                    data.check_state.remove(&key)
+                } else {
+                    data.check_state.insert(key)
+                }
+            }
+        }
+    };
+    if !notify {
+        return Response::reason(204, "Skipped.");
+    }
+    forward_event(data, settings, event).await
 }
 
 async fn route_post(
     mut request: HttpRequest<Body>,
+    Extension(endpoint): Extension<Endpoint>,
     Extension(configure): Extension<Config>,
     Extension(data): Extension<Arc<RwLock<ExtraData>>>,
 ) -> impl IntoResponse {
@@ -117,75 +342,78 @@ async fn route_post(
 
     let body = body;
 
-    let object = serde_json::from_slice::<GitHubEarlyParse>(&body);
-    if let Err(ref e) = object {
-        error!("Get parser error in pre-check stage: {:?}", &e);
-        error!("Raw data => {:?}", String::from_utf8_lossy(&body));
-        return Response::new(500);
+    let provider = match endpoint.provider() {
+        Some(kind) => provider_of(kind),
+        None => match detect_provider(request.headers()) {
+            Some(provider) => provider,
+            None => {
+                error!("Unknown request: {:?}", request);
+                return Response::new(500);
+            }
+        },
+    };
+    route_webhook(
+        provider,
+        endpoint.secrets().as_deref(),
+        request.headers(),
+        &body,
+        &configure,
+        &data,
+    )
+    .await
+}
+
+async fn route_webhook(
+    provider: &dyn WebhookProvider,
+    default_secrets: Option<&str>,
+    headers: &HeaderMap,
+    body: &[u8],
+    configure: &Config,
+    data: &Arc<RwLock<ExtraData>>,
+) -> Response {
+    let event_type = match provider.event_type(headers) {
+        Some(event_type) => event_type,
+        None => {
+            error!("Event type not found in headers: {:?}", headers);
+            return Response::new(500);
+        }
     };
-    let object = object.unwrap();
-    let settings = configure.fetch_repository_configure(object.get_full_name());
+
+    let full_name = match provider.full_name(&event_type, body) {
+        Ok(full_name) => full_name,
+        Err(e) => {
+            error!("Get parser error in pre-check stage: {:?}", &e);
+            error!("Raw data => {:?}", String::from_utf8_lossy(body));
+            return Response::new(500);
+        }
+    };
+    let mut settings = configure.fetch_repository_configure(&full_name);
+    if let Some(secrets) = default_secrets {
+        settings.set_default_secrets(secrets);
+    }
 
     let secrets = settings.secrets();
     if !secrets.is_empty() {
-        type HmacSha256 = Hmac<Sha256>;
-        let mut h = HmacSha256::new_from_slice(secrets.as_bytes()).unwrap();
-        h.update(&*body);
-        let result = h.finalize();
-        let sha256val = format!("sha256={:x}", result.into_bytes()).to_lowercase();
-        if let Some(val) = request.headers().get("X-Hub-Signature-256") {
-            if !sha256val.eq(val) {
-                return Response::reason(403, "Checksum error");
-            }
-        } else {
-            return Response::reason(403, "Checksum header not found");
+        if let Err(reason) = provider.verify_signature(headers, body, secrets) {
+            return Response::reason(403, reason);
         }
     }
 
-    let event_header = request.headers().get("X-GitHub-Event");
-    if event_header.is_none() {
-        error!("Unknown request: {:?}", request);
-        return Response::new(500);
-    }
-    let event_header = event_header.unwrap().to_str();
-    if let Err(ref e) = event_header {
-        error!("Parse X-GitHub-Event error: {:?}", e);
-        return Response::new(500);
-    }
-    let event_header = event_header.unwrap();
-    match event_header {
-        "ping" => {
-            let request_body = match serde_json::from_slice::<GitHubPingEvent>(&body) {
-                Ok(ret) => ret,
-                Err(e) => return Response::new_parse_error(e),
-            };
-            Response::reason(200, request_body.zen())
-        }
-        "push" => {
-            let event = match serde_json::from_slice::<GitHubPushEvent>(&body) {
-                Ok(ret) => ret,
-                Err(e) => return Response::new_parse_error(e),
-            };
-            if check_0(event.after()) || check_0(event.before()) {
-                return Response::new_empty();
-            }
-            if settings.branch_ignore().contains(&event.branch_name()) {
-                Response::reason(204, "Skipped.")
-            } else {
-                let sender = data.write().await;
-                sender
-                    .bot_tx
-                    .send(Command::Bundle(CommandBundle::new(
-                        settings.send_to().clone(),
-                        event.to_string(),
-                    )))
-                    .await
-                    .unwrap();
-                Response::new_ok()
+    let events = match provider.parse(&event_type, body, &settings) {
+        Ok(events) => events,
+        Err(response) => return response,
+    };
+    let mut response = Response::new_empty();
+    for event in events {
+        response = match event {
+            NormalizedEvent::Notify(event) => forward_event(data, &settings, &event).await,
+            NormalizedEvent::Security(event) => {
+                forward_security_event(data, &settings, &event).await
             }
-        }
-        _ => Response::reason(400, format!("Unsupported event type {:?}", event_header)),
+            NormalizedEvent::Check(event) => forward_check_event(data, &settings, &event).await,
+        };
     }
+    response
 }
 
 async fn async_main<P: AsRef<Path>>(path: P) -> anyhow::Result<()> {
@@ -195,8 +423,29 @@ async fn async_main<P: AsRef<Path>>(path: P) -> anyhow::Result<()> {
 
     AUTH_TOKEN.set(config.server().token().to_string()).unwrap();
 
+    let mut sinks = Sinks::default();
+    let mut sink_senders = Vec::new();
+    for kind in SinkKind::ALL {
+        let (tx, rx) = mpsc::channel(1024);
+        match sink_daemon(&config, kind, rx) {
+            Some(daemon) => {
+                sinks.insert(kind, tx);
+                sink_senders.push(spawn_sink(kind.name(), daemon));
+            }
+            None if config.is_sink_used(kind) => {
+                warn!(
+                    "{} sink is not configured, its receivers are ignored",
+                    kind.name()
+                )
+            }
+            None => {}
+        }
+    }
+
     let extra_data = Arc::new(RwLock::new(ExtraData {
         bot_tx: bot_tx.clone(),
+        sinks,
+        check_state: CheckState::default(),
     }));
     let msg_sender = tokio::spawn(process_send_message(
         config.telegram().bot_token().to_string(),
@@ -207,17 +456,27 @@ async fn async_main<P: AsRef<Path>>(path: P) -> anyhow::Result<()> {
     let bind = config.server().bind().clone();
     info!("Bind address: {}", bind);
 
-    let router = Router::new()
-        .route(
-            "/",
-            axum::routing::post(route_post)
-                .layer(axum::middleware::from_extractor::<AuthorizationGuard>())
-                .layer(Extension(config.clone()))
-                .layer(Extension(extra_data.clone())),
-        )
+    let webhook = |endpoint: Endpoint| {
+        axum::routing::post(route_post)
+            .layer(axum::middleware::from_extractor::<AuthorizationGuard>())
+            .layer(Extension(endpoint))
+            .layer(Extension(config.clone()))
+            .layer(Extension(extra_data.clone()))
+    };
+
+    let mut router = Router::new()
+        .route("/", webhook(Endpoint::default()))
         .route("/", axum::routing::get(|| async { Response::new_ok() }))
-        .route("/", axum::routing::any(|| async { StatusCode::FORBIDDEN }))
-        .layer(ServiceBuilder::new().layer(TraceLayer::new_for_http()));
+        .route("/", axum::routing::any(|| async { StatusCode::FORBIDDEN }));
+    for endpoint in config.endpoints() {
+        info!(
+            "Listen {:?} webhook on {}",
+            endpoint.provider().unwrap(),
+            endpoint.path()
+        );
+        router = router.route(endpoint.path(), webhook(endpoint.clone()));
+    }
+    let router = router.layer(ServiceBuilder::new().layer(TraceLayer::new_for_http()));
 
     let handler = axum_server::Handle::new();
 
@@ -237,13 +496,21 @@ async fn async_main<P: AsRef<Path>>(path: P) -> anyhow::Result<()> {
     }
 
     bot_tx.send(Command::Terminate).await?;
+    extra_data.read().await.sinks.terminate().await;
 
     tokio::select! {
         _ = tokio::signal::ctrl_c() => {
             warn!("Force exit from message sender");
         }
-        ret = msg_sender => {
-            ret??;
+        ret = async {
+            msg_sender.await??;
+            // Errors of sinks are logged by `spawn_sink`, wait for the rest to exit
+            for sender in sink_senders {
+                sender.await?.ok();
+            }
+            anyhow::Ok(())
+        } => {
+            ret?;
         }
     }
 
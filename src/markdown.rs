@@ -0,0 +1,84 @@
+/*
+ ** Copyright (C) 2021 KunoiSayami
+ **
+ ** This program is free software: you can redistribute it and/or modify
+ ** it under the terms of the GNU Affero General Public License as published by
+ ** the Free Software Foundation, either version 3 of the License, or
+ ** any later version.
+ **
+ ** This program is distributed in the hope that it will be useful,
+ ** but WITHOUT ANY WARRANTY; without even the implied warranty of
+ ** MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ ** GNU Affero General Public License for more details.
+ **
+ ** You should have received a copy of the GNU Affero General Public License
+ ** along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::datastructures::escape_html;
+use pulldown_cmark::{Event, Options, Parser, Tag};
+
+/// Convert markdown to the HTML subset supported by Telegram
+/// (`b`, `i`, `s`, `code`, `pre` and `a`), anything else is flattened to plain text.
+pub fn markdown_to_telegram_html(s: &str) -> String {
+    let mut output = String::new();
+    // Stack of list counters, `None` for unordered list
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    for event in Parser::new_ext(s, Options::ENABLE_STRIKETHROUGH) {
+        match event {
+            Event::Start(tag) => match tag {
+                Tag::Heading(..) | Tag::Strong => output.push_str("<b>"),
+                Tag::Emphasis => output.push_str("<i>"),
+                Tag::Strikethrough => output.push_str("<s>"),
+                Tag::CodeBlock(_) => output.push_str("<pre>"),
+                Tag::Link(_, url, _) => {
+                    output.push_str(&format!("<a href=\"{}\">", escape_html(&url)))
+                }
+                Tag::List(start) => lists.push(start),
+                Tag::Item => {
+                    if !output.is_empty() && !output.ends_with('\n') {
+                        output.push('\n');
+                    }
+                    output.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                    match lists.last_mut() {
+                        Some(Some(n)) => {
+                            output.push_str(&format!("{}. ", n));
+                            *n += 1;
+                        }
+                        _ => output.push_str("• "),
+                    }
+                }
+                _ => {}
+            },
+            Event::End(tag) => match tag {
+                Tag::Heading(..) => output.push_str("</b>\n\n"),
+                Tag::Strong => output.push_str("</b>"),
+                Tag::Emphasis => output.push_str("</i>"),
+                Tag::Strikethrough => output.push_str("</s>"),
+                Tag::CodeBlock(_) => output.push_str("</pre>\n\n"),
+                Tag::Link(..) => output.push_str("</a>"),
+                Tag::Paragraph => output.push_str("\n\n"),
+                Tag::List(_) => {
+                    lists.pop();
+                    if lists.is_empty() {
+                        output.push_str("\n\n");
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(text) | Event::Html(text) => output.push_str(&escape_html(&text)),
+            Event::Code(text) => output.push_str(&format!("<code>{}</code>", escape_html(&text))),
+            Event::SoftBreak | Event::HardBreak => output.push('\n'),
+            Event::Rule => output.push_str("——————\n\n"),
+            Event::TaskListMarker(checked) => {
+                output.push_str(if checked { "☑ " } else { "☐ " })
+            }
+            Event::FootnoteReference(name) => output.push_str(&format!("[{}]", escape_html(&name))),
+        }
+        // Keep at most one blank line between blocks
+        if output.ends_with("\n\n\n") {
+            output.pop();
+        }
+    }
+    output.trim_end().to_string()
+}
@@ -0,0 +1,497 @@
+/*
+ ** Copyright (C) 2021 KunoiSayami
+ **
+ ** This program is free software: you can redistribute it and/or modify
+ ** it under the terms of the GNU Affero General Public License as published by
+ ** the Free Software Foundation, either version 3 of the License, or
+ ** any later version.
+ **
+ ** This program is distributed in the hope that it will be useful,
+ ** but WITHOUT ANY WARRANTY; without even the implied warranty of
+ ** MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ ** GNU Affero General Public License for more details.
+ **
+ ** You should have received a copy of the GNU Affero General Public License
+ ** along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::bitbucket::BitbucketProvider;
+use crate::configure::{ProviderKind, Repository as RepositoryConfig};
+use crate::datastructures::{
+    CheckEvent, DisplayableEvent, GitHubCheckSuiteEvent, GitHubCodeScanningAlertEvent,
+    GitHubCommitCommentEvent, GitHubDependabotAlertEvent, GitHubDeploymentEvent,
+    GitHubDeploymentStatusEvent, GitHubDiscussionCommentEvent, GitHubDiscussionEvent,
+    GitHubEarlyParse, GitHubForkEvent, GitHubGenericEvent, GitHubGollumEvent,
+    GitHubIssueCommentEvent, GitHubIssuesEvent, GitHubMemberEvent, GitHubPingEvent,
+    GitHubPublicEvent, GitHubPullRequestEvent, GitHubPullRequestReviewCommentEvent,
+    GitHubPullRequestReviewEvent, GitHubPushEvent, GitHubReleaseEvent, GitHubRepositoryEvent,
+    GitHubRepositoryVulnerabilityAlertEvent, GitHubSecretScanningAlertEvent, GitHubStarEvent,
+    GitHubStatusEvent, GitHubWatchEvent, GitHubWorkflowJobEvent, GitHubWorkflowRunEvent, PushKind,
+    Response,
+};
+use crate::gitlab::GitLabProvider;
+use axum::http::HeaderMap;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+// Events which will be forwarded only if listed in `extra_events` of repository
+const OPTIONAL_EVENTS: [&str; 8] = [
+    "star",
+    "watch",
+    "fork",
+    "member",
+    "public",
+    "repository",
+    "workflow_job",
+    "check_suite",
+];
+
+/// Event parsed by provider, tells how it should be forwarded.
+#[derive(Debug)]
+pub enum NormalizedEvent {
+    /// Send to `send_to` of repository
+    Notify(Box<dyn DisplayableEvent>),
+    /// Send to `security_send_to` of repository
+    Security(Box<dyn DisplayableEvent>),
+    /// CI result, filtered by `actions_notify` of repository
+    Check(Box<dyn CheckEvent>),
+}
+
+pub trait WebhookProvider: Send + Sync {
+    /// `None` if this request is not sent by this provider.
+    fn event_type(&self, headers: &HeaderMap) -> Option<String>;
+
+    /// Repository name used to look up `[[repository]]` configure.
+    fn full_name(&self, event_type: &str, body: &[u8]) -> serde_json::Result<String>;
+
+    /// Error is the reason of rejecting this request.
+    fn verify_signature(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        secrets: &str,
+    ) -> Result<(), &'static str>;
+
+    /// Filtered by repository settings, `Err` is responded as is (e.g. ping or skipped event).
+    fn parse(
+        &self,
+        event_type: &str,
+        body: &[u8],
+        settings: &RepositoryConfig,
+    ) -> Result<Vec<NormalizedEvent>, Response>;
+}
+
+pub fn provider_of(kind: ProviderKind) -> &'static dyn WebhookProvider {
+    match kind {
+        ProviderKind::GitHub => &GitHubProvider,
+        ProviderKind::GitLab => &GitLabProvider,
+        ProviderKind::Gitea => &GiteaProvider,
+        ProviderKind::Bitbucket => &BitbucketProvider,
+    }
+}
+
+/// Find provider by request headers, used by endpoints without specific provider.
+pub fn detect_provider(headers: &HeaderMap) -> Option<&'static dyn WebhookProvider> {
+    // Gitea also sends `X-GitHub-Event`, so it should be checked before GitHub
+    [
+        ProviderKind::GitLab,
+        ProviderKind::Bitbucket,
+        ProviderKind::Gitea,
+        ProviderKind::GitHub,
+    ]
+    .into_iter()
+    .map(provider_of)
+    .find(|x| x.event_type(headers).is_some())
+}
+
+pub fn hmac_sha256_hex(secrets: &str, body: &[u8]) -> String {
+    type HmacSha256 = Hmac<Sha256>;
+    let mut h = HmacSha256::new_from_slice(secrets.as_bytes()).unwrap();
+    h.update(body);
+    format!("{:x}", h.finalize().into_bytes())
+}
+
+pub fn check_header(headers: &HeaderMap, key: &str, expected: &str) -> Result<(), &'static str> {
+    match headers.get(key) {
+        Some(val) if expected.eq(val) => Ok(()),
+        Some(_) => Err("Checksum error"),
+        None => Err("Checksum header not found"),
+    }
+}
+
+pub fn header_str(headers: &HeaderMap, key: &str) -> Option<String> {
+    headers
+        .get(key)
+        .and_then(|x| x.to_str().ok())
+        .map(|x| x.to_string())
+}
+
+pub fn is_push_enabled(settings: &RepositoryConfig, kind: PushKind, created: bool) -> bool {
+    match kind {
+        PushKind::Tag => settings.notify_tag_push() && created,
+        PushKind::BranchCreated => settings.notify_branch_create(),
+        PushKind::BranchDeleted => settings.notify_branch_delete(),
+        PushKind::ForcePushed => settings.notify_force_push(),
+        PushKind::Commits => true,
+    }
+}
+
+pub struct GitHubProvider;
+
+impl WebhookProvider for GitHubProvider {
+    fn event_type(&self, headers: &HeaderMap) -> Option<String> {
+        header_str(headers, "X-GitHub-Event")
+    }
+
+    fn full_name(&self, _event_type: &str, body: &[u8]) -> serde_json::Result<String> {
+        Ok(serde_json::from_slice::<GitHubEarlyParse>(body)?
+            .get_full_name()
+            .clone())
+    }
+
+    fn verify_signature(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        secrets: &str,
+    ) -> Result<(), &'static str> {
+        check_header(
+            headers,
+            "X-Hub-Signature-256",
+            &format!("sha256={}", hmac_sha256_hex(secrets, body)),
+        )
+    }
+
+    fn parse(
+        &self,
+        event_header: &str,
+        body: &[u8],
+        settings: &RepositoryConfig,
+    ) -> Result<Vec<NormalizedEvent>, Response> {
+        if OPTIONAL_EVENTS.contains(&event_header)
+            && !settings.extra_events().iter().any(|x| x.eq(event_header))
+        {
+            return Err(Response::reason(204, "Skipped."));
+        }
+        match event_header {
+            "ping" => {
+                let request_body = match serde_json::from_slice::<GitHubPingEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                Err(Response::reason(200, request_body.zen()))
+            }
+            "push" => {
+                let event = match serde_json::from_slice::<GitHubPushEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                if !is_push_enabled(settings, event.kind(), event.created()) {
+                    return Err(Response::new_empty());
+                }
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "pull_request" => {
+                let event = match serde_json::from_slice::<GitHubPullRequestEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                if !settings
+                    .pull_request_actions()
+                    .iter()
+                    .any(|x| x.eq(event.action_kind()))
+                {
+                    return Err(Response::reason(204, "Skipped."));
+                }
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "pull_request_review" => {
+                let mut event = match serde_json::from_slice::<GitHubPullRequestReviewEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                // Reviews which only contain inline comments are sent by `pull_request_review_comment`
+                if event.action() != "submitted"
+                    || (event.review().state() == "commented"
+                        && event
+                            .review()
+                            .body()
+                            .as_deref()
+                            .unwrap_or_default()
+                            .is_empty())
+                {
+                    return Err(Response::reason(204, "Skipped."));
+                }
+                event.set_comment_length(settings.comment_length());
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "pull_request_review_comment" => {
+                let mut event =
+                    match serde_json::from_slice::<GitHubPullRequestReviewCommentEvent>(body) {
+                        Ok(ret) => ret,
+                        Err(e) => return Err(Response::new_parse_error(e)),
+                    };
+                if event.action() != "created" {
+                    return Err(Response::reason(204, "Skipped."));
+                }
+                event.set_comment_length(settings.comment_length());
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "issues" => {
+                let event = match serde_json::from_slice::<GitHubIssuesEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                if !settings
+                    .issue_actions()
+                    .iter()
+                    .any(|x| x.eq(event.action()))
+                {
+                    return Err(Response::reason(204, "Skipped."));
+                }
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "issue_comment" => {
+                let mut event = match serde_json::from_slice::<GitHubIssueCommentEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                if event.action() != "created" {
+                    return Err(Response::reason(204, "Skipped."));
+                }
+                event.set_comment_length(settings.comment_length());
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "discussion" => {
+                let event = match serde_json::from_slice::<GitHubDiscussionEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                if !settings
+                    .discussion_actions()
+                    .iter()
+                    .any(|x| x.eq(event.action()))
+                {
+                    return Err(Response::reason(204, "Skipped."));
+                }
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "discussion_comment" => {
+                let mut event = match serde_json::from_slice::<GitHubDiscussionCommentEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                if event.action() != "created" {
+                    return Err(Response::reason(204, "Skipped."));
+                }
+                event.set_comment_length(settings.comment_length());
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "release" => {
+                let mut event = match serde_json::from_slice::<GitHubReleaseEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                if event.action() != "published" {
+                    return Err(Response::reason(204, "Skipped."));
+                }
+                event.set_notes_length(settings.comment_length());
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "workflow_run" => {
+                let event = match serde_json::from_slice::<GitHubWorkflowRunEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                Ok(vec![NormalizedEvent::Check(Box::new(event))])
+            }
+            "workflow_job" => {
+                let event = match serde_json::from_slice::<GitHubWorkflowJobEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                Ok(vec![NormalizedEvent::Check(Box::new(event))])
+            }
+            "check_suite" => {
+                let event = match serde_json::from_slice::<GitHubCheckSuiteEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                Ok(vec![NormalizedEvent::Check(Box::new(event))])
+            }
+            "status" => {
+                let event = match serde_json::from_slice::<GitHubStatusEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                Ok(vec![NormalizedEvent::Check(Box::new(event))])
+            }
+            "commit_comment" => {
+                let mut event = match serde_json::from_slice::<GitHubCommitCommentEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                if event.action() != "created" {
+                    return Err(Response::reason(204, "Skipped."));
+                }
+                event.set_comment_length(settings.comment_length());
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "gollum" => {
+                let event = match serde_json::from_slice::<GitHubGollumEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "deployment" => {
+                let event = match serde_json::from_slice::<GitHubDeploymentEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                if !settings.is_deployment_environment_enabled(event.deployment().environment()) {
+                    return Err(Response::reason(204, "Skipped."));
+                }
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "deployment_status" => {
+                let event = match serde_json::from_slice::<GitHubDeploymentStatusEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                if !matches!(
+                    event.deployment_status().state(),
+                    "success" | "failure" | "error"
+                ) || !settings
+                    .is_deployment_environment_enabled(event.deployment().environment())
+                {
+                    return Err(Response::reason(204, "Skipped."));
+                }
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "dependabot_alert" => {
+                let event = match serde_json::from_slice::<GitHubDependabotAlertEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                Ok(vec![NormalizedEvent::Security(Box::new(event))])
+            }
+            "code_scanning_alert" => {
+                let event = match serde_json::from_slice::<GitHubCodeScanningAlertEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                Ok(vec![NormalizedEvent::Security(Box::new(event))])
+            }
+            "secret_scanning_alert" => {
+                let event = match serde_json::from_slice::<GitHubSecretScanningAlertEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                Ok(vec![NormalizedEvent::Security(Box::new(event))])
+            }
+            "repository_vulnerability_alert" => {
+                let event =
+                    match serde_json::from_slice::<GitHubRepositoryVulnerabilityAlertEvent>(body) {
+                        Ok(ret) => ret,
+                        Err(e) => return Err(Response::new_parse_error(e)),
+                    };
+                Ok(vec![NormalizedEvent::Security(Box::new(event))])
+            }
+            "star" => {
+                let event = match serde_json::from_slice::<GitHubStarEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "watch" => {
+                let event = match serde_json::from_slice::<GitHubWatchEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "fork" => {
+                let event = match serde_json::from_slice::<GitHubForkEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "member" => {
+                let event = match serde_json::from_slice::<GitHubMemberEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "public" => {
+                let event = match serde_json::from_slice::<GitHubPublicEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            "repository" => {
+                let event = match serde_json::from_slice::<GitHubRepositoryEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            _ if settings.forward_unknown_events() => {
+                let mut event = match serde_json::from_slice::<GitHubGenericEvent>(body) {
+                    Ok(ret) => ret,
+                    Err(e) => return Err(Response::new_parse_error(e)),
+                };
+                event.set_event(event_header);
+                Ok(vec![NormalizedEvent::Notify(Box::new(event))])
+            }
+            _ => Err(Response::reason(
+                400,
+                format!("Unsupported event type {:?}", event_header),
+            )),
+        }
+    }
+}
+
+/// Gitea (and Forgejo) send GitHub-like payloads, only the headers differ.
+pub struct GiteaProvider;
+
+impl WebhookProvider for GiteaProvider {
+    fn event_type(&self, headers: &HeaderMap) -> Option<String> {
+        let event_type = header_str(headers, "X-Gitea-Event")?;
+        Some(match event_type.as_str() {
+            // Comments on pull requests are sent with issue_comment payload
+            "pull_request_comment" => "issue_comment".to_string(),
+            _ => event_type,
+        })
+    }
+
+    fn full_name(&self, event_type: &str, body: &[u8]) -> serde_json::Result<String> {
+        GitHubProvider.full_name(event_type, body)
+    }
+
+    fn verify_signature(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        secrets: &str,
+    ) -> Result<(), &'static str> {
+        check_header(
+            headers,
+            "X-Gitea-Signature",
+            &hmac_sha256_hex(secrets, body),
+        )
+    }
+
+    fn parse(
+        &self,
+        event_type: &str,
+        body: &[u8],
+        settings: &RepositoryConfig,
+    ) -> Result<Vec<NormalizedEvent>, Response> {
+        GitHubProvider.parse(event_type, body, settings)
+    }
+}
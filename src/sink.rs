@@ -0,0 +1,386 @@
+/*
+ ** Copyright (C) 2021 KunoiSayami
+ **
+ ** This program is free software: you can redistribute it and/or modify
+ ** it under the terms of the GNU Affero General Public License as published by
+ ** the Free Software Foundation, either version 3 of the License, or
+ ** any later version.
+ **
+ ** This program is distributed in the hope that it will be useful,
+ ** but WITHOUT ANY WARRANTY; without even the implied warranty of
+ ** MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ ** GNU Affero General Public License for more details.
+ **
+ ** You should have received a copy of the GNU Affero General Public License
+ ** along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::datastructures::{escape_html, truncate, CommandBundle, EventKind, Notification};
+use crate::Command;
+use log::{debug, error, warn};
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::mpsc;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+pub mod discord;
+pub mod email;
+pub mod gotify;
+pub mod irc;
+pub mod matrix;
+pub mod ntfy;
+pub mod slack;
+pub mod teams;
+pub mod webhook;
+pub mod xmpp;
+
+/// Urgency of push notifications, mapped to the priority scale of each service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    Min,
+    Low,
+    Default,
+    High,
+}
+
+impl From<EventKind> for Priority {
+    fn from(kind: EventKind) -> Self {
+        match kind {
+            EventKind::CheckFailure | EventKind::Security => Priority::High,
+            EventKind::Check | EventKind::Wiki | EventKind::Repository | EventKind::Other => {
+                Priority::Low
+            }
+            EventKind::Star | EventKind::Fork => Priority::Min,
+            _ => Priority::Default,
+        }
+    }
+}
+
+/// Target format of `convert_html`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Markup {
+    Plain,
+    /// Markdown flavor used by Discord
+    Discord,
+    /// Slack `mrkdwn`
+    Slack,
+    /// Sanitized HTML with `<br>` line breaks, used by Matrix
+    Html,
+    /// Markdown subset of Adaptive Card `TextBlock`, which has no code span
+    Teams,
+    /// IRC formatting codes, links are dropped since they make lines too long
+    Irc,
+    /// XHTML-IM (XEP-0071), which recommends `strong` and `em` instead of `b` and `i`
+    Xhtml,
+}
+
+impl Markup {
+    fn tag(&self, name: &str, closing: bool, href: &str) -> String {
+        match self {
+            Markup::Plain => String::new(),
+            Markup::Html => match (name, closing) {
+                ("a", false) => format!("<a href=\"{}\">", escape_html(href)),
+                (name, false) => format!("<{}>", name),
+                (name, true) => format!("</{}>", name),
+            },
+            Markup::Discord => match (name, closing) {
+                ("b", _) => "**".to_string(),
+                ("i", _) => "_".to_string(),
+                ("u", _) => "__".to_string(),
+                ("s", _) => "~~".to_string(),
+                ("code", _) => "`".to_string(),
+                ("pre", false) => "```\n".to_string(),
+                ("pre", true) => "\n```".to_string(),
+                ("a", false) => "[".to_string(),
+                ("a", true) => format!("]({})", href),
+                _ => String::new(),
+            },
+            Markup::Teams => match (name, closing) {
+                ("b", _) => "**".to_string(),
+                ("i", _) => "_".to_string(),
+                ("a", false) => "[".to_string(),
+                ("a", true) => format!("]({})", href),
+                _ => String::new(),
+            },
+            Markup::Xhtml => match (name, closing) {
+                ("a", false) => format!("<a href=\"{}\">", escape_html(href)),
+                ("b", false) => "<strong>".to_string(),
+                ("i", false) => "<em>".to_string(),
+                ("u", false) => "<span style=\"text-decoration: underline;\">".to_string(),
+                ("s", false) => "<span style=\"text-decoration: line-through;\">".to_string(),
+                ("code" | "pre", false) => "<code>".to_string(),
+                ("a", true) => "</a>".to_string(),
+                ("b", true) => "</strong>".to_string(),
+                ("i", true) => "</em>".to_string(),
+                ("u" | "s", true) => "</span>".to_string(),
+                ("code" | "pre", true) => "</code>".to_string(),
+                _ => String::new(),
+            },
+            Markup::Irc => match name {
+                "b" => "\x02".to_string(),
+                "i" => "\x1d".to_string(),
+                "u" => "\x1f".to_string(),
+                "s" => "\x1e".to_string(),
+                _ => String::new(),
+            },
+            Markup::Slack => match (name, closing) {
+                ("b", _) => "*".to_string(),
+                ("i", _) => "_".to_string(),
+                ("s", _) => "~".to_string(),
+                ("code", _) => "`".to_string(),
+                ("pre", _) => "```".to_string(),
+                ("a", false) => format!("<{}|", href.replace('|', "%7C")),
+                ("a", true) => ">".to_string(),
+                _ => String::new(),
+            },
+        }
+    }
+
+    /// Escape plain text, text inside code spans is kept as is.
+    pub fn escape(&self, s: &str, in_code: bool) -> String {
+        match self {
+            Markup::Discord if !in_code => {
+                let mut output = String::with_capacity(s.len());
+                for c in s.chars() {
+                    if matches!(c, '\\' | '*' | '_' | '~' | '`' | '|') {
+                        output.push('\\');
+                    }
+                    output.push(c);
+                }
+                output
+            }
+            // Only these characters have to be escaped, formatting characters can not be escaped
+            Markup::Slack => s
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;"),
+            Markup::Html if in_code => escape_html(s),
+            Markup::Html => escape_html(s).replace('\n', "<br>"),
+            // Line breaks are not kept by XHTML-IM, even in code
+            Markup::Xhtml => escape_html(s).replace('\n', "<br/>"),
+            // Line breaks would start a new command
+            Markup::Irc => s.replace(['\r', '\n', '\0'], " "),
+            _ => s.to_string(),
+        }
+    }
+}
+
+pub fn unescape_html(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Split tag content like `a href="..."` into name, closing flag and `href`.
+fn parse_tag(s: &str) -> Option<(&str, bool, Option<&str>)> {
+    let (closing, s) = match s.strip_prefix('/') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let (name, attributes) = s.split_once(' ').unwrap_or((s, ""));
+    if !matches!(name, "a" | "b" | "i" | "u" | "s" | "code" | "pre") {
+        return None;
+    }
+    let href = attributes
+        .trim()
+        .strip_prefix("href=\"")
+        .and_then(|x| x.strip_suffix('"'));
+    Some((name, closing, href))
+}
+
+/// Convert the Telegram HTML generated by events to other markup,
+/// unknown tags (e.g. in unescaped commit messages) are kept as text.
+pub fn convert_html(html: &str, markup: Markup) -> String {
+    let mut output = String::with_capacity(html.len());
+    // Stack of opened links, the URL is written by closing tag in some markups
+    let mut links: Vec<String> = Vec::new();
+    let mut in_code = false;
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        output.push_str(&markup.escape(&unescape_html(&rest[..start]), in_code));
+        rest = &rest[start..];
+        let tag = rest
+            .find('>')
+            .and_then(|end| parse_tag(&rest[1..end]).map(|tag| (end, tag)));
+        match tag {
+            Some((end, (name, closing, href))) => {
+                let href = match (name, closing) {
+                    ("a", false) => {
+                        links.push(unescape_html(href.unwrap_or_default()));
+                        links.last().cloned().unwrap_or_default()
+                    }
+                    ("a", true) => links.pop().unwrap_or_default(),
+                    _ => String::new(),
+                };
+                if matches!(name, "code" | "pre") {
+                    in_code = !closing;
+                }
+                output.push_str(&markup.tag(name, closing, &href));
+                rest = &rest[end + 1..];
+            }
+            None => {
+                output.push_str(&markup.escape("<", in_code));
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(&markup.escape(&unescape_html(rest), in_code));
+    output
+}
+
+pub fn html_to_plain(html: &str) -> String {
+    convert_html(html, Markup::Plain)
+}
+
+/// Shorten `s` to `limit` characters, including the ellipsis.
+pub fn limit(s: &str, limit: usize) -> String {
+    truncate(s, Some(limit - 1))
+}
+
+/// `repository:branch`, or repository only if the event is not related to any branch.
+pub fn source(notification: &Notification) -> String {
+    match notification.branch() {
+        "" => notification.repository().to_string(),
+        branch => format!("{}:{}", notification.repository(), branch),
+    }
+}
+
+/// Message without the first line in plain text, `source` if there is nothing else.
+pub fn plain_body(notification: &Notification) -> String {
+    match notification.text().split_once('\n') {
+        Some((_, rest)) if !rest.trim().is_empty() => html_to_plain(rest.trim()),
+        _ => source(notification),
+    }
+}
+
+// Seconds to wait before reconnecting, doubled on each failure
+pub const RECONNECT_DELAY_MIN: u64 = 1;
+pub const RECONNECT_DELAY_MAX: u64 = 300;
+
+/// Plain or TLS connection of sinks which keep a connection, e.g. IRC and XMPP.
+pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+/// Verify servers with the bundled Mozilla root certificates.
+pub fn tls_connector() -> anyhow::Result<TlsConnector> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config = ClientConfig::builder_with_provider(Arc::new(
+        tokio_rustls::rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+// Most messages kept while disconnected, the oldest ones are dropped first
+const PENDING_LIMIT: usize = 256;
+
+/// Keep `bundle` until it is sent, the oldest message is dropped and returned
+/// if too many are waiting.
+pub fn push_pending(
+    pending: &mut VecDeque<CommandBundle<String>>,
+    bundle: CommandBundle<String>,
+) -> Option<CommandBundle<String>> {
+    let dropped = if pending.len() >= PENDING_LIMIT {
+        warn!("Too many pending messages, dropped the oldest one");
+        pending.pop_front()
+    } else {
+        None
+    };
+    pending.push_back(bundle);
+    dropped
+}
+
+/// Wait before reconnecting, messages received meanwhile are kept in `pending`.
+/// Return `false` if terminated while waiting.
+pub async fn wait_reconnect(
+    delay: Duration,
+    pending: &mut VecDeque<CommandBundle<String>>,
+    rx: &mut mpsc::Receiver<Command<String>>,
+) -> bool {
+    let sleep = tokio::time::sleep(delay);
+    tokio::pin!(sleep);
+    loop {
+        tokio::select! {
+            _ = &mut sleep => return true,
+            cmd = rx.recv() => match cmd {
+                Some(Command::Bundle(bundle)) => {
+                    push_pending(pending, bundle);
+                }
+                Some(Command::Terminate) | None => return false,
+            },
+        }
+    }
+}
+
+// Seconds to wait for a response of webhooks which have no timeout setting
+pub const HTTP_TIMEOUT: u64 = 10;
+
+/// Client of HTTP sinks, requests are given up after `timeout` seconds so one slow
+/// server does not hold up the following messages.
+pub fn http_client(timeout: u64) -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(timeout))
+        .build()
+}
+
+// Longest `Retry-After` in seconds to honor, larger values are clamped
+const RETRY_AFTER_MAX: f64 = 60.0;
+
+/// Post `payload` to `url`, retry once if rate limited.
+pub async fn post_json(client: &reqwest::Client, url: &str, payload: &Value) -> anyhow::Result<()> {
+    for _ in 0..2 {
+        let response = client.post(url).json(payload).send().await?;
+        if response.status() != StatusCode::TOO_MANY_REQUESTS {
+            response.error_for_status()?;
+            return Ok(());
+        }
+        let retry_after = response
+            .headers()
+            .get("Retry-After")
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.parse::<f64>().ok())
+            .filter(|x| x.is_finite() && *x >= 0.0)
+            .unwrap_or(1.0)
+            .min(RETRY_AFTER_MAX);
+        debug!("Rate limited by {}, retry after {}s", url, retry_after);
+        tokio::time::sleep(Duration::from_secs_f64(retry_after)).await;
+    }
+    Err(anyhow::anyhow!("Rate limited"))
+}
+
+/// Daemon of sinks which post JSON built by `build` to webhook URLs,
+/// each request is given up after `timeout` seconds.
+pub async fn process_json_webhook(
+    name: &str,
+    mut rx: mpsc::Receiver<Command<String>>,
+    build: fn(&Notification) -> Value,
+    timeout: u64,
+) -> anyhow::Result<()> {
+    let client = http_client(timeout)?;
+    while let Some(cmd) = rx.recv().await {
+        match cmd {
+            Command::Bundle(bundle) => {
+                let payload = build(bundle.notification());
+                for url in bundle.receiver() {
+                    if let Err(e) = post_json(&client, url, &payload).await {
+                        error!("Got error in send {} message {:?}", name, e);
+                    }
+                }
+            }
+            Command::Terminate => break,
+        }
+    }
+    debug!("{} message daemon exiting...", name);
+    Ok(())
+}
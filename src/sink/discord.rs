@@ -0,0 +1,96 @@
+/*
+ ** Copyright (C) 2021 KunoiSayami
+ **
+ ** This program is free software: you can redistribute it and/or modify
+ ** it under the terms of the GNU Affero General Public License as published by
+ ** the Free Software Foundation, either version 3 of the License, or
+ ** any later version.
+ **
+ ** This program is distributed in the hope that it will be useful,
+ ** but WITHOUT ANY WARRANTY; without even the implied warranty of
+ ** MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ ** GNU Affero General Public License for more details.
+ **
+ ** You should have received a copy of the GNU Affero General Public License
+ ** along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::datastructures::{short_hash, EventKind, Notification};
+use crate::sink::{convert_html, limit, process_json_webhook, source, Markup, HTTP_TIMEOUT};
+use crate::Command;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+// Limits of Discord embed, see https://discord.com/developers/docs/resources/message#embed-object-embed-limits
+const TITLE_LIMIT: usize = 256;
+const DESCRIPTION_LIMIT: usize = 4096;
+const FIELD_NAME_LIMIT: usize = 256;
+const FIELD_VALUE_LIMIT: usize = 1024;
+const FIELDS_LIMIT: usize = 25;
+
+fn color(kind: EventKind) -> u32 {
+    match kind {
+        EventKind::Push | EventKind::Branch => 0x3498db,
+        EventKind::Tag | EventKind::Release => 0x9b59b6,
+        EventKind::PullRequest | EventKind::Check => 0x2ecc71,
+        EventKind::Review | EventKind::Comment | EventKind::Discussion => 0xf1c40f,
+        EventKind::Issue => 0xe67e22,
+        EventKind::Deployment => 0x1abc9c,
+        EventKind::CheckFailure | EventKind::Security => 0xe74c3c,
+        EventKind::Star | EventKind::Fork => 0xffd700,
+        EventKind::Wiki | EventKind::Repository | EventKind::Other => 0x95a5a6,
+    }
+}
+
+/// Build the webhook body, commits are listed as fields instead of description.
+pub fn build_payload(notification: &Notification) -> Value {
+    let mut embed = json!({
+        "title": limit(&notification.title(), TITLE_LIMIT),
+        "color": color(notification.kind()),
+        "footer": {"text": source(notification)},
+    });
+    if let Some(url) = notification.url() {
+        embed["url"] = json!(url);
+    }
+    if let Some(actor) = notification.actor() {
+        embed["author"] = json!({ "name": actor });
+    }
+    if notification.commits().is_empty() {
+        let description = match notification.text().split_once('\n') {
+            Some((_, rest)) => convert_html(rest.trim(), Markup::Discord),
+            None => String::new(),
+        };
+        if !description.is_empty() {
+            embed["description"] = json!(limit(&description, DESCRIPTION_LIMIT));
+        }
+    } else {
+        embed["fields"] = notification
+            .commits()
+            .iter()
+            .take(FIELDS_LIMIT)
+            .map(|commit| {
+                let title = commit.message().lines().next().unwrap_or_default();
+                json!({
+                    "name": limit(short_hash(commit.id()), FIELD_NAME_LIMIT),
+                    "value": limit(
+                        &format!(
+                            "[{}]({})",
+                            Markup::Discord.escape(title, false),
+                            commit.url()
+                        ),
+                        FIELD_VALUE_LIMIT,
+                    ),
+                })
+            })
+            .collect();
+    }
+    json!({
+        "embeds": [embed],
+        // Never ping anyone mentioned in commit messages or comments
+        "allowed_mentions": {"parse": []},
+    })
+}
+
+pub async fn process_discord_message(rx: mpsc::Receiver<Command<String>>) -> anyhow::Result<()> {
+    process_json_webhook("discord", rx, build_payload, HTTP_TIMEOUT).await
+}
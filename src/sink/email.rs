@@ -0,0 +1,182 @@
+/*
+ ** Copyright (C) 2021 KunoiSayami
+ **
+ ** This program is free software: you can redistribute it and/or modify
+ ** it under the terms of the GNU Affero General Public License as published by
+ ** the Free Software Foundation, either version 3 of the License, or
+ ** any later version.
+ **
+ ** This program is distributed in the hope that it will be useful,
+ ** but WITHOUT ANY WARRANTY; without even the implied warranty of
+ ** MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ ** GNU Affero General Public License for more details.
+ **
+ ** You should have received a copy of the GNU Affero General Public License
+ ** along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::configure::{Smtp, SmtpSecurity};
+use crate::datastructures::{CommandBundle, Notification};
+use crate::sink::{convert_html, html_to_plain, push_pending, Markup};
+use crate::Command;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use log::{debug, error};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+type Mailer = AsyncSmtpTransport<Tokio1Executor>;
+
+fn build_transport(smtp: &Smtp) -> anyhow::Result<Mailer> {
+    let builder = match smtp.security() {
+        SmtpSecurity::StartTls => Mailer::starttls_relay(smtp.host())?,
+        SmtpSecurity::Tls => Mailer::relay(smtp.host())?,
+        SmtpSecurity::None => Mailer::builder_dangerous(smtp.host()),
+    };
+    let builder = builder.port(smtp.port());
+    let builder = match smtp.username() {
+        Some(username) => builder.credentials(Credentials::new(
+            username.clone(),
+            smtp.password().to_string(),
+        )),
+        None => builder,
+    };
+    Ok(builder.build())
+}
+
+/// Build an email of one or more notifications, with plain text alternative.
+/// `dropped` is the number of older notifications dropped from the digest.
+pub fn build_message(
+    from: &Mailbox,
+    to: &str,
+    notifications: &[Notification],
+    dropped: usize,
+) -> anyhow::Result<Message> {
+    let subject = match notifications {
+        [notification] if dropped == 0 => notification.title(),
+        _ => format!("Digest of {} events", notifications.len() + dropped),
+    };
+    let mut html = notifications
+        .iter()
+        .map(|x| format!("<p>{}</p>", convert_html(x.text(), Markup::Html)))
+        .collect::<Vec<String>>();
+    let mut plain = notifications
+        .iter()
+        .map(|x| html_to_plain(x.text()))
+        .collect::<Vec<String>>();
+    if dropped > 0 {
+        let note = format!(
+            "{} older events were dropped, too many events were waiting for this digest.",
+            dropped
+        );
+        html.insert(0, format!("<p><i>{}</i></p>", note));
+        plain.insert(0, note);
+    }
+    let html = html.join("\n<hr>\n");
+    let plain = plain.join("\n\n----\n\n");
+    Ok(Message::builder()
+        .from(from.clone())
+        .to(to.parse()?)
+        .subject(subject)
+        .multipart(MultiPart::alternative_plain_html(
+            plain,
+            format!("<html><body>\n{}\n</body></html>", html),
+        ))?)
+}
+
+async fn send(
+    mailer: &Mailer,
+    from: &Mailbox,
+    to: &str,
+    notifications: &[Notification],
+    dropped: usize,
+) {
+    let result = match build_message(from, to, notifications, dropped) {
+        Ok(message) => mailer.send(message).await.map_err(anyhow::Error::from),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        error!("Got error in send email to {} {:?}", to, e);
+    }
+}
+
+/// Send and clear pending notifications, one email per recipient.
+async fn send_digest(
+    mailer: &Mailer,
+    from: &Mailbox,
+    pending: &mut VecDeque<CommandBundle<String>>,
+    dropped: &mut BTreeMap<String, usize>,
+) {
+    // Recipients whose notifications are all dropped still get the number of them
+    let mut digests: BTreeMap<String, Vec<Notification>> =
+        dropped.keys().map(|to| (to.clone(), Vec::new())).collect();
+    for bundle in pending.drain(..) {
+        for to in bundle.receiver() {
+            digests
+                .entry(to.clone())
+                .or_default()
+                .push(bundle.notification().clone());
+        }
+    }
+    for (to, notifications) in digests {
+        let dropped = dropped.get(&to).copied().unwrap_or_default();
+        send(mailer, from, &to, &notifications, dropped).await;
+    }
+    dropped.clear();
+}
+
+pub async fn process_email_message(
+    smtp: Smtp,
+    mut rx: mpsc::Receiver<Command<String>>,
+) -> anyhow::Result<()> {
+    let mailer = build_transport(&smtp)?;
+    let from = smtp.sender().clone();
+    // Notifications waiting for digest, the oldest ones are dropped if too many are waiting
+    let mut pending: VecDeque<CommandBundle<String>> = VecDeque::new();
+    // Number of dropped notifications by recipient
+    let mut dropped: BTreeMap<String, usize> = BTreeMap::new();
+    let mut interval = smtp.digest_interval().map(|minutes| {
+        let period = Duration::from_secs(minutes * 60);
+        tokio::time::interval_at(Instant::now() + period, period)
+    });
+    loop {
+        let cmd = match interval.as_mut() {
+            Some(interval) => tokio::select! {
+                cmd = rx.recv() => cmd,
+                _ = interval.tick() => {
+                    send_digest(&mailer, &from, &mut pending, &mut dropped).await;
+                    continue;
+                }
+            },
+            None => rx.recv().await,
+        };
+        match cmd {
+            Some(Command::Bundle(bundle)) if interval.is_some() => {
+                if let Some(bundle) = push_pending(&mut pending, bundle) {
+                    for to in bundle.receiver() {
+                        *dropped.entry(to.clone()).or_default() += 1;
+                    }
+                }
+            }
+            Some(Command::Bundle(bundle)) => {
+                for to in bundle.receiver() {
+                    send(
+                        &mailer,
+                        &from,
+                        to,
+                        std::slice::from_ref(bundle.notification()),
+                        0,
+                    )
+                    .await;
+                }
+            }
+            Some(Command::Terminate) | None => break,
+        }
+    }
+    send_digest(&mailer, &from, &mut pending, &mut dropped).await;
+    debug!("Email message daemon exiting...");
+    Ok(())
+}
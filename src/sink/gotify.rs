@@ -0,0 +1,80 @@
+/*
+ ** Copyright (C) 2021 KunoiSayami
+ **
+ ** This program is free software: you can redistribute it and/or modify
+ ** it under the terms of the GNU Affero General Public License as published by
+ ** the Free Software Foundation, either version 3 of the License, or
+ ** any later version.
+ **
+ ** This program is distributed in the hope that it will be useful,
+ ** but WITHOUT ANY WARRANTY; without even the implied warranty of
+ ** MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ ** GNU Affero General Public License for more details.
+ **
+ ** You should have received a copy of the GNU Affero General Public License
+ ** along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::configure::Gotify;
+use crate::datastructures::Notification;
+use crate::sink::{http_client, plain_body, Priority};
+use crate::Command;
+use log::{debug, error};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+// Android client shows a heads-up notification from 8, and is silent below 4
+fn priority(priority: Priority) -> u8 {
+    match priority {
+        Priority::Min => 0,
+        Priority::Low => 2,
+        Priority::Default => 5,
+        Priority::High => 8,
+    }
+}
+
+pub fn build_payload(notification: &Notification) -> Value {
+    let mut payload = json!({
+        "title": notification.title(),
+        "message": plain_body(notification),
+        "priority": priority(Priority::from(notification.kind())),
+        "extras": {
+            "client::display": {"contentType": "text/plain"},
+        },
+    });
+    if let Some(url) = notification.url() {
+        payload["extras"]["client::notification"] = json!({ "click": { "url": url } });
+    }
+    payload
+}
+
+/// Receivers are application tokens, each one posts to its own application.
+pub async fn process_gotify_message(
+    gotify: Gotify,
+    mut rx: mpsc::Receiver<Command<String>>,
+) -> anyhow::Result<()> {
+    let client = http_client(gotify.timeout())?;
+    let url = format!("{}/message", gotify.server());
+    while let Some(cmd) = rx.recv().await {
+        match cmd {
+            Command::Bundle(bundle) => {
+                let payload = build_payload(bundle.notification());
+                for token in bundle.receiver() {
+                    let result = client
+                        .post(&url)
+                        .header("X-Gotify-Key", token)
+                        .json(&payload)
+                        .send()
+                        .await
+                        .and_then(|x| x.error_for_status());
+                    if let Err(e) = result {
+                        error!("Got error in send gotify message {:?}", e);
+                    }
+                }
+            }
+            Command::Terminate => break,
+        }
+    }
+    debug!("gotify message daemon exiting...");
+    Ok(())
+}
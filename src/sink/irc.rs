@@ -0,0 +1,424 @@
+/*
+ ** Copyright (C) 2021 KunoiSayami
+ **
+ ** This program is free software: you can redistribute it and/or modify
+ ** it under the terms of the GNU Affero General Public License as published by
+ ** the Free Software Foundation, either version 3 of the License, or
+ ** any later version.
+ **
+ ** This program is distributed in the hope that it will be useful,
+ ** but WITHOUT ANY WARRANTY; without even the implied warranty of
+ ** MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ ** GNU Affero General Public License for more details.
+ **
+ ** You should have received a copy of the GNU Affero General Public License
+ ** along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::configure::Irc;
+use crate::datastructures::{CommandBundle, Notification};
+use crate::sink::{
+    convert_html, push_pending, tls_connector, wait_reconnect, Markup, Stream, RECONNECT_DELAY_MAX,
+    RECONNECT_DELAY_MIN,
+};
+use crate::Command;
+use log::{debug, error, info, warn};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines, ReadHalf, WriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tokio_rustls::rustls::pki_types::ServerName;
+
+// A line is limited to 512 bytes, including the prefix added by server when relaying
+const LINE_LIMIT: usize = 400;
+const LINES_LIMIT: usize = 10;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+// A JOIN neither echoed nor refused by server is given up after this
+const JOIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Channel and text of `PRIVMSG` which are not sent yet, kept across reconnections
+/// so a bundle partly sent is continued instead of sent again.
+type Outbox = VecDeque<(String, String)>;
+
+/// Split `s` into lines of at most `limit` bytes, at a space if possible.
+fn split_line(s: &str, limit: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut rest = s;
+    while rest.len() > limit {
+        let mut end = limit;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        if let Some(space) = rest[..end].rfind(' ').filter(|x| *x > limit / 2) {
+            end = space;
+        }
+        lines.push(rest[..end].to_string());
+        rest = rest[end..].trim_start();
+    }
+    lines.push(rest.to_string());
+    lines
+}
+
+/// Lines of `PRIVMSG`, the link of event is appended to the first line.
+pub fn build_lines(notification: &Notification) -> Vec<String> {
+    let mut lines = notification
+        .text()
+        .lines()
+        .filter(|x| !x.trim().is_empty())
+        .map(|x| convert_html(x, Markup::Irc))
+        .collect::<Vec<String>>();
+    if let (Some(first), Some(url)) = (lines.first_mut(), notification.url()) {
+        first.push_str(&format!(" {}", Markup::Irc.escape(url, true)));
+    }
+    let mut lines = lines
+        .iter()
+        .flat_map(|x| split_line(x, LINE_LIMIT))
+        .collect::<Vec<String>>();
+    if lines.len() > LINES_LIMIT {
+        let more = lines.len() - LINES_LIMIT + 1;
+        lines.truncate(LINES_LIMIT - 1);
+        lines.push(format!("... and {} more lines", more));
+    }
+    lines
+}
+
+/// Split a line from server into command and parameters, the prefix is dropped.
+fn parse_message(line: &str) -> (&str, Vec<&str>) {
+    let line = match line.strip_prefix(':') {
+        Some(line) => line.split_once(' ').map_or("", |x| x.1),
+        None => line,
+    };
+    let (line, trailing) = match line.split_once(" :") {
+        Some((line, trailing)) => (line, Some(trailing)),
+        None => (line, None),
+    };
+    let mut params = line
+        .split(' ')
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<&str>>();
+    let command = if params.is_empty() {
+        ""
+    } else {
+        params.remove(0)
+    };
+    params.extend(trailing);
+    (command, params)
+}
+
+/// Nickname in the prefix of a line from server.
+fn parse_source(line: &str) -> &str {
+    line.strip_prefix(':')
+        .and_then(|x| x.split([' ', '!', '@']).next())
+        .unwrap_or_default()
+}
+
+/// Same as the message timer of ircd, `burst` lines can be sent at once,
+/// then one line per `interval`.
+struct FloodControl {
+    interval: Duration,
+    burst: u32,
+    next: Instant,
+}
+
+impl FloodControl {
+    fn new(irc: &Irc) -> Self {
+        Self {
+            interval: Duration::from_millis(irc.flood_interval()),
+            burst: irc.flood_burst(),
+            next: Instant::now(),
+        }
+    }
+
+    /// Time when the next line can be sent.
+    fn ready_at(&self) -> Instant {
+        let window = self.interval * self.burst;
+        self.next.checked_sub(window).unwrap_or(self.next)
+    }
+
+    fn consume(&mut self) {
+        self.next = self.next.max(Instant::now()) + self.interval;
+    }
+}
+
+struct Connection {
+    reader: Lines<BufReader<ReadHalf<Box<dyn Stream>>>>,
+    writer: WriteHalf<Box<dyn Stream>>,
+    flood: FloodControl,
+    nickname: String,
+    // Channels are kept in lowercase
+    joined: HashSet<String>,
+    joining: HashMap<String, Instant>,
+    failed: Vec<String>,
+    to_join: VecDeque<String>,
+}
+
+impl Connection {
+    async fn connect(irc: &Irc) -> anyhow::Result<Self> {
+        let tcp = tokio::time::timeout(
+            CONNECT_TIMEOUT,
+            TcpStream::connect((irc.server(), irc.port())),
+        )
+        .await??;
+        let stream: Box<dyn Stream> = if irc.tls() {
+            let server_name = ServerName::try_from(irc.server().to_string())?;
+            Box::new(tls_connector()?.connect(server_name, tcp).await?)
+        } else {
+            Box::new(tcp)
+        };
+        let (reader, writer) = tokio::io::split(stream);
+        let mut connection = Self {
+            reader: BufReader::new(reader).lines(),
+            writer,
+            flood: FloodControl::new(irc),
+            nickname: irc.nickname().to_string(),
+            joined: HashSet::new(),
+            joining: HashMap::new(),
+            failed: Vec::new(),
+            to_join: VecDeque::new(),
+        };
+        tokio::time::timeout(CONNECT_TIMEOUT, connection.register(irc)).await??;
+        Ok(connection)
+    }
+
+    /// Send without flood control, the caller takes care of it.
+    async fn send_raw(&mut self, line: &str) -> anyhow::Result<()> {
+        self.writer
+            .write_all(format!("{}\r\n", line).as_bytes())
+            .await?;
+        self.writer.flush().await?;
+        Ok(())
+    }
+
+    async fn next_line(&mut self) -> anyhow::Result<String> {
+        self.reader
+            .next_line()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Connection closed by server"))
+    }
+
+    /// Wait for `RPL_WELCOME`, `_` is appended to nickname until it is accepted.
+    async fn register(&mut self, irc: &Irc) -> anyhow::Result<()> {
+        if let Some(password) = irc.password() {
+            self.send_raw(&format!("PASS :{}", password)).await?;
+        }
+        self.send_raw(&format!("NICK {}", self.nickname)).await?;
+        self.send_raw(&format!("USER {} 0 * :{}", irc.username(), irc.realname()))
+            .await?;
+        loop {
+            let line = self.next_line().await?;
+            match parse_message(&line) {
+                ("001", params) => {
+                    if let Some(nickname) = params.first() {
+                        self.nickname = nickname.to_string();
+                    }
+                    return Ok(());
+                }
+                // ERR_NICKNAMEINUSE
+                ("433", _) => {
+                    self.nickname.push('_');
+                    self.send_raw(&format!("NICK {}", self.nickname)).await?;
+                }
+                _ => self.handle(&line).await?,
+            }
+        }
+    }
+
+    async fn handle(&mut self, line: &str) -> anyhow::Result<()> {
+        match parse_message(line) {
+            ("PING", params) => {
+                self.send_raw(&format!("PONG :{}", params.last().unwrap_or(&"")))
+                    .await?
+            }
+            ("ERROR", params) => {
+                return Err(anyhow::anyhow!(
+                    "Closed by server: {}",
+                    params.last().unwrap_or(&"")
+                ))
+            }
+            ("JOIN", params)
+                if !params.is_empty()
+                    && parse_source(line).eq_ignore_ascii_case(&self.nickname) =>
+            {
+                let channel = params[0].to_ascii_lowercase();
+                self.joining.remove(&channel);
+                self.joined.insert(channel);
+            }
+            // Join again before next message
+            ("KICK", params)
+                if params
+                    .get(1)
+                    .is_some_and(|x| x.eq_ignore_ascii_case(&self.nickname)) =>
+            {
+                warn!("Kicked from {}: {:?}", params[0], params.get(2));
+                self.joined.remove(&params[0].to_ascii_lowercase());
+            }
+            // ERR_CANNOTSENDTOCHAN, most likely the bot is not in channel any more
+            ("404", params) if params.len() > 1 => {
+                warn!(
+                    "Cannot send to {}: {}, join again before next message",
+                    params[1],
+                    params.last().unwrap_or(&"")
+                );
+                self.joined.remove(&params[1].to_ascii_lowercase());
+            }
+            // ERR_NOSUCHCHANNEL, ERR_TOOMANYCHANNELS, ERR_UNAVAILRESOURCE, ERR_CHANNELISFULL,
+            // ERR_INVITEONLYCHAN, ERR_BANNEDFROMCHAN, ERR_BADCHANNELKEY, ERR_BADCHANMASK,
+            // ERR_NEEDREGGEDNICK
+            ("403" | "405" | "437" | "471" | "473" | "474" | "475" | "476" | "477", params)
+                if params.len() > 1
+                    && self
+                        .joining
+                        .remove(&params[1].to_ascii_lowercase())
+                        .is_some() =>
+            {
+                warn!(
+                    "Failed to join {}: {}",
+                    params[1],
+                    params.last().unwrap_or(&"")
+                );
+                self.failed.push(params[1].to_ascii_lowercase());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Time when the earliest pending JOIN is given up.
+    fn join_deadline(&self) -> Option<Instant> {
+        self.joining.values().min().copied()
+    }
+
+    /// Prepare the next line for `send_next`, return `false` if nothing can be sent now.
+    /// Messages to a channel which cannot be joined are dropped, and the channel is joined
+    /// again for the next bundle.
+    fn prepare(
+        &mut self,
+        outbox: &mut Outbox,
+        pending: &mut VecDeque<CommandBundle<String>>,
+    ) -> bool {
+        let now = Instant::now();
+        self.joining.retain(|channel, deadline| {
+            if *deadline > now {
+                return true;
+            }
+            warn!("Timeout when joining {}", channel);
+            self.failed.push(channel.clone());
+            false
+        });
+        for channel in self.failed.drain(..) {
+            let count = outbox.len();
+            outbox.retain(|(x, _)| x.to_ascii_lowercase() != channel);
+            if count != outbox.len() {
+                warn!("Dropped {} lines to {}", count - outbox.len(), channel);
+            }
+        }
+        if !self.to_join.is_empty() {
+            return true;
+        }
+        if outbox.is_empty() {
+            if let Some(bundle) = pending.pop_front() {
+                let lines = build_lines(bundle.notification());
+                for channel in bundle.receiver() {
+                    outbox.extend(lines.iter().map(|x| (channel.clone(), x.clone())));
+                }
+            }
+        }
+        let channel = match outbox.front() {
+            Some((channel, _)) => channel,
+            None => return false,
+        };
+        let key = channel.to_ascii_lowercase();
+        if self.joined.contains(&key) {
+            return true;
+        }
+        if self.joining.contains_key(&key) {
+            return false;
+        }
+        self.to_join.push_back(channel.clone());
+        true
+    }
+
+    /// Send the JOIN or PRIVMSG prepared by `prepare`, a channel is joined once
+    /// server echoes the JOIN back.
+    async fn send_next(&mut self, outbox: &mut Outbox) -> anyhow::Result<()> {
+        self.flood.consume();
+        if let Some(channel) = self.to_join.pop_front() {
+            self.send_raw(&format!("JOIN {}", channel)).await?;
+            self.joining
+                .insert(channel.to_ascii_lowercase(), Instant::now() + JOIN_TIMEOUT);
+        } else if let Some((channel, line)) = outbox.front() {
+            self.send_raw(&format!("PRIVMSG {} :{}", channel, line))
+                .await?;
+            outbox.pop_front();
+        }
+        Ok(())
+    }
+}
+
+/// Serve until terminated, messages are kept in `pending` and `outbox` until they are sent.
+/// Lines from server are handled while messages are waiting for flood control.
+async fn serve(
+    irc: &Irc,
+    channels: &[String],
+    outbox: &mut Outbox,
+    pending: &mut VecDeque<CommandBundle<String>>,
+    rx: &mut mpsc::Receiver<Command<String>>,
+) -> anyhow::Result<()> {
+    let mut connection = Connection::connect(irc).await?;
+    info!(
+        "Connected to IRC server {} as {}",
+        irc.server(),
+        connection.nickname
+    );
+    connection.to_join.extend(channels.iter().cloned());
+    loop {
+        let ready = connection.prepare(outbox, pending);
+        let ready_at = connection.flood.ready_at();
+        let join_deadline = connection.join_deadline();
+        tokio::select! {
+            line = connection.next_line() => connection.handle(&line?).await?,
+            _ = tokio::time::sleep_until(ready_at), if ready => connection.send_next(outbox).await?,
+            // Checked by `prepare`
+            _ = tokio::time::sleep_until(join_deadline.unwrap_or(ready_at)), if join_deadline.is_some() => {}
+            cmd = rx.recv() => match cmd {
+                Some(Command::Bundle(bundle)) => {
+                    push_pending(pending, bundle);
+                }
+                Some(Command::Terminate) | None => {
+                    connection.send_raw("QUIT :Bye").await.ok();
+                    return Ok(());
+                }
+            },
+        }
+    }
+}
+
+/// Keep a connection to IRC server, `channels` are joined once connected.
+pub async fn process_irc_message(
+    irc: Irc,
+    channels: Vec<String>,
+    mut rx: mpsc::Receiver<Command<String>>,
+) -> anyhow::Result<()> {
+    let mut outbox = Outbox::new();
+    let mut pending = VecDeque::new();
+    let mut delay = RECONNECT_DELAY_MIN;
+    loop {
+        let started = Instant::now();
+        let e = match serve(&irc, &channels, &mut outbox, &mut pending, &mut rx).await {
+            Ok(_) => break,
+            Err(e) => e,
+        };
+        if started.elapsed() > Duration::from_secs(RECONNECT_DELAY_MAX) {
+            delay = RECONNECT_DELAY_MIN;
+        }
+        error!("IRC connection lost: {:?}, reconnect after {}s", e, delay);
+        if !wait_reconnect(Duration::from_secs(delay), &mut pending, &mut rx).await {
+            break;
+        }
+        delay = (delay * 2).min(RECONNECT_DELAY_MAX);
+    }
+    debug!("IRC message daemon exiting...");
+    Ok(())
+}
@@ -0,0 +1,132 @@
+/*
+ ** Copyright (C) 2021 KunoiSayami
+ **
+ ** This program is free software: you can redistribute it and/or modify
+ ** it under the terms of the GNU Affero General Public License as published by
+ ** the Free Software Foundation, either version 3 of the License, or
+ ** any later version.
+ **
+ ** This program is distributed in the hope that it will be useful,
+ ** but WITHOUT ANY WARRANTY; without even the implied warranty of
+ ** MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ ** GNU Affero General Public License for more details.
+ **
+ ** You should have received a copy of the GNU Affero General Public License
+ ** along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::configure::Matrix;
+use crate::datastructures::Notification;
+use crate::sink::{convert_html, html_to_plain, http_client, Markup};
+use crate::Command;
+use log::{debug, error};
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+
+struct MatrixClient {
+    client: reqwest::Client,
+    homeserver: Url,
+    access_token: String,
+    // Room ID by room ID or alias in configure, only rooms which are joined
+    rooms: HashMap<String, String>,
+    // Transaction IDs must be unique for each access token
+    txn_prefix: u128,
+    txn_count: u64,
+}
+
+impl MatrixClient {
+    fn new(matrix: &Matrix) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: http_client(matrix.timeout())?,
+            homeserver: Url::parse(matrix.homeserver())?,
+            access_token: matrix.access_token().to_string(),
+            rooms: HashMap::new(),
+            txn_prefix: SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis(),
+            txn_count: 0,
+        })
+    }
+
+    /// Build URL of client-server API, segments are percent-encoded.
+    fn url(&self, segments: &[&str]) -> anyhow::Result<Url> {
+        let mut url = self.homeserver.clone();
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("Homeserver {} cannot be a base URL", self.homeserver))?
+            .pop_if_empty()
+            .extend(["_matrix", "client", "v3"])
+            .extend(segments);
+        Ok(url)
+    }
+
+    /// Join the room once, an alias is resolved to room ID by joining.
+    async fn join(&mut self, room: &str) -> anyhow::Result<String> {
+        if let Some(room_id) = self.rooms.get(room) {
+            return Ok(room_id.clone());
+        }
+        let response: Value = self
+            .client
+            .post(self.url(&["join", room])?)
+            .bearer_auth(&self.access_token)
+            .json(&json!({}))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let room_id = response
+            .get("room_id")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Room ID not found in {:?}", response))?
+            .to_string();
+        self.rooms.insert(room.to_string(), room_id.clone());
+        Ok(room_id)
+    }
+
+    async fn send(&mut self, room: &str, payload: &Value) -> anyhow::Result<()> {
+        let room_id = self.join(room).await?;
+        self.txn_count += 1;
+        let txn_id = format!("{}.{}", self.txn_prefix, self.txn_count);
+        self.client
+            .put(self.url(&["rooms", &room_id, "send", "m.room.message", &txn_id])?)
+            .bearer_auth(&self.access_token)
+            .json(payload)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Sent as `m.notice`, which is used by bots and never triggers other bots.
+pub fn build_payload(notification: &Notification) -> Value {
+    json!({
+        "msgtype": "m.notice",
+        "body": html_to_plain(notification.text()),
+        "format": "org.matrix.custom.html",
+        "formatted_body": convert_html(notification.text(), Markup::Html),
+    })
+}
+
+pub async fn process_matrix_message(
+    matrix: Matrix,
+    mut rx: mpsc::Receiver<Command<String>>,
+) -> anyhow::Result<()> {
+    let mut client = MatrixClient::new(&matrix)?;
+    while let Some(cmd) = rx.recv().await {
+        match cmd {
+            Command::Bundle(bundle) => {
+                let payload = build_payload(bundle.notification());
+                for room in bundle.receiver() {
+                    if let Err(e) = client.send(room, &payload).await {
+                        error!("Got error in send matrix message to {} {:?}", room, e);
+                    }
+                }
+            }
+            Command::Terminate => break,
+        }
+    }
+    debug!("Matrix message daemon exiting...");
+    Ok(())
+}
@@ -0,0 +1,92 @@
+/*
+ ** Copyright (C) 2021 KunoiSayami
+ **
+ ** This program is free software: you can redistribute it and/or modify
+ ** it under the terms of the GNU Affero General Public License as published by
+ ** the Free Software Foundation, either version 3 of the License, or
+ ** any later version.
+ **
+ ** This program is distributed in the hope that it will be useful,
+ ** but WITHOUT ANY WARRANTY; without even the implied warranty of
+ ** MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ ** GNU Affero General Public License for more details.
+ **
+ ** You should have received a copy of the GNU Affero General Public License
+ ** along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::configure::Ntfy;
+use crate::datastructures::Notification;
+use crate::sink::{http_client, limit, plain_body, Priority};
+use crate::Command;
+use log::{debug, error};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+// Messages larger than 4096 bytes are sent as attachments by ntfy
+const MESSAGE_LIMIT: usize = 2000;
+
+fn priority(priority: Priority) -> u8 {
+    match priority {
+        Priority::Min => 1,
+        Priority::Low => 2,
+        Priority::Default => 3,
+        Priority::High => 4,
+    }
+}
+
+/// Build the body of JSON publishing, `topic` is filled by `send`.
+pub fn build_payload(notification: &Notification) -> Value {
+    let mut payload = json!({
+        "title": notification.title(),
+        "message": limit(&plain_body(notification), MESSAGE_LIMIT),
+        "priority": priority(Priority::from(notification.kind())),
+    });
+    if let Some(url) = notification.url() {
+        payload["click"] = json!(url);
+    }
+    payload
+}
+
+/// `topic` is a topic name on the configured server, or a topic URL on another server,
+/// the access token is only sent to the configured server.
+async fn send(
+    client: &reqwest::Client,
+    ntfy: &Ntfy,
+    topic: &str,
+    mut payload: Value,
+) -> anyhow::Result<()> {
+    let (server, topic, token) = match topic.rsplit_once('/') {
+        Some((server, topic)) if server.contains("://") => (server, topic, None),
+        _ => (ntfy.server(), topic, ntfy.token().as_ref()),
+    };
+    payload["topic"] = json!(topic);
+    let mut request = client.post(server).json(&payload);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    request.send().await?.error_for_status()?;
+    Ok(())
+}
+
+pub async fn process_ntfy_message(
+    ntfy: Ntfy,
+    mut rx: mpsc::Receiver<Command<String>>,
+) -> anyhow::Result<()> {
+    let client = http_client(ntfy.timeout())?;
+    while let Some(cmd) = rx.recv().await {
+        match cmd {
+            Command::Bundle(bundle) => {
+                let payload = build_payload(bundle.notification());
+                for topic in bundle.receiver() {
+                    if let Err(e) = send(&client, &ntfy, topic, payload.clone()).await {
+                        error!("Got error in send ntfy message {:?}", e);
+                    }
+                }
+            }
+            Command::Terminate => break,
+        }
+    }
+    debug!("ntfy message daemon exiting...");
+    Ok(())
+}
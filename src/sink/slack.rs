@@ -0,0 +1,50 @@
+/*
+ ** Copyright (C) 2021 KunoiSayami
+ **
+ ** This program is free software: you can redistribute it and/or modify
+ ** it under the terms of the GNU Affero General Public License as published by
+ ** the Free Software Foundation, either version 3 of the License, or
+ ** any later version.
+ **
+ ** This program is distributed in the hope that it will be useful,
+ ** but WITHOUT ANY WARRANTY; without even the implied warranty of
+ ** MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ ** GNU Affero General Public License for more details.
+ **
+ ** You should have received a copy of the GNU Affero General Public License
+ ** along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::datastructures::Notification;
+use crate::sink::{convert_html, limit, process_json_webhook, source, Markup, HTTP_TIMEOUT};
+use crate::Command;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+const SECTION_TEXT_LIMIT: usize = 3000;
+
+/// Build the webhook body, `text` is kept for Mattermost and Rocket.Chat, which ignore `blocks`.
+pub fn build_payload(notification: &Notification) -> Value {
+    let text = convert_html(notification.text(), Markup::Slack);
+    json!({
+        "text": text,
+        "blocks": [
+            {
+                "type": "section",
+                "text": {"type": "mrkdwn", "text": limit(&text, SECTION_TEXT_LIMIT)},
+            },
+            {
+                "type": "context",
+                "elements": [
+                    {"type": "mrkdwn", "text": Markup::Slack.escape(&source(notification), false)},
+                ],
+            },
+        ],
+        "unfurl_links": false,
+        "unfurl_media": false,
+    })
+}
+
+pub async fn process_slack_message(rx: mpsc::Receiver<Command<String>>) -> anyhow::Result<()> {
+    process_json_webhook("slack", rx, build_payload, HTTP_TIMEOUT).await
+}
@@ -0,0 +1,84 @@
+/*
+ ** Copyright (C) 2021 KunoiSayami
+ **
+ ** This program is free software: you can redistribute it and/or modify
+ ** it under the terms of the GNU Affero General Public License as published by
+ ** the Free Software Foundation, either version 3 of the License, or
+ ** any later version.
+ **
+ ** This program is distributed in the hope that it will be useful,
+ ** but WITHOUT ANY WARRANTY; without even the implied warranty of
+ ** MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ ** GNU Affero General Public License for more details.
+ **
+ ** You should have received a copy of the GNU Affero General Public License
+ ** along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::configure::Teams;
+use crate::datastructures::{EventKind, Notification};
+use crate::sink::{convert_html, process_json_webhook, Markup};
+use crate::Command;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+/// Build an Adaptive Card message, accepted by both incoming webhooks and Workflows.
+pub fn build_payload(notification: &Notification) -> Value {
+    let mut body = vec![json!({
+        "type": "TextBlock",
+        "text": notification.title(),
+        "weight": "Bolder",
+        "size": "Medium",
+        "wrap": true,
+    })];
+    if let Some((_, rest)) = notification.text().split_once('\n') {
+        // Each line needs its own paragraph, single line break is ignored
+        let text = rest
+            .lines()
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| convert_html(x, Markup::Teams))
+            .collect::<Vec<String>>()
+            .join("\n\n");
+        if !text.is_empty() {
+            body.push(json!({"type": "TextBlock", "text": text, "wrap": true}));
+        }
+    }
+    let mut facts = vec![json!({"title": "Repository", "value": notification.repository()})];
+    if !notification.branch().is_empty() {
+        facts.push(json!({"title": "Branch", "value": notification.branch()}));
+    }
+    if let Some(actor) = notification.actor() {
+        facts.push(json!({"title": "Author", "value": actor}));
+    }
+    body.push(json!({"type": "FactSet", "facts": facts}));
+
+    let mut card = json!({
+        "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+        "type": "AdaptiveCard",
+        "version": "1.4",
+        "body": body,
+    });
+    if let Some(url) = notification.url() {
+        // URL of push events is the compare view
+        let title = match notification.kind() {
+            EventKind::Push | EventKind::Branch | EventKind::Tag => "View compare",
+            _ => "View details",
+        };
+        card["actions"] = json!([{"type": "Action.OpenUrl", "title": title, "url": url}]);
+    }
+    json!({
+        "type": "message",
+        "attachments": [{
+            "contentType": "application/vnd.microsoft.card.adaptive",
+            "contentUrl": null,
+            "content": card,
+        }],
+    })
+}
+
+pub async fn process_teams_message(
+    teams: Teams,
+    rx: mpsc::Receiver<Command<String>>,
+) -> anyhow::Result<()> {
+    process_json_webhook("teams", rx, build_payload, teams.timeout()).await
+}
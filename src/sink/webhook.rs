@@ -0,0 +1,140 @@
+/*
+ ** Copyright (C) 2021 KunoiSayami
+ **
+ ** This program is free software: you can redistribute it and/or modify
+ ** it under the terms of the GNU Affero General Public License as published by
+ ** the Free Software Foundation, either version 3 of the License, or
+ ** any later version.
+ **
+ ** This program is distributed in the hope that it will be useful,
+ ** but WITHOUT ANY WARRANTY; without even the implied warranty of
+ ** MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ ** GNU Affero General Public License for more details.
+ **
+ ** You should have received a copy of the GNU Affero General Public License
+ ** along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::configure::Webhook;
+use crate::datastructures::{EventKind, Notification};
+use crate::provider::hmac_sha256_hex;
+use crate::sink::{html_to_plain, http_client};
+use crate::Command;
+use log::{debug, error};
+use reqwest::header::{CONTENT_TYPE, USER_AGENT};
+use serde_derive::Serialize;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+
+/// Bump if a field is removed or changes its meaning, adding fields does not.
+pub const PAYLOAD_VERSION: u32 = 1;
+
+/// Body posted by the generic webhook sink, see README for the schema.
+#[derive(Serialize, Debug)]
+pub struct WebhookPayload<'a> {
+    version: u32,
+    kind: EventKind,
+    repository: &'a str,
+    /// `None` for tags and events not related to a branch
+    branch: Option<&'a str>,
+    actor: Option<&'a str>,
+    url: Option<&'a str>,
+    title: String,
+    /// Whole message without markup
+    text: String,
+    /// Whole message in Telegram HTML
+    html: &'a str,
+    commits: Vec<WebhookCommit<'a>>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct WebhookCommit<'a> {
+    id: &'a str,
+    message: &'a str,
+    url: &'a str,
+}
+
+impl<'a> From<&'a Notification> for WebhookPayload<'a> {
+    fn from(notification: &'a Notification) -> Self {
+        Self {
+            version: PAYLOAD_VERSION,
+            kind: notification.kind(),
+            repository: notification.repository(),
+            // Tag name is in `branch` of notification
+            branch: Some(notification.branch())
+                .filter(|x| !x.is_empty() && notification.kind() != EventKind::Tag),
+            actor: notification.actor().as_deref(),
+            url: notification.url().as_deref(),
+            title: notification.title(),
+            text: html_to_plain(notification.text()),
+            html: notification.text(),
+            commits: notification
+                .commits()
+                .iter()
+                .map(|x| WebhookCommit {
+                    id: x.id(),
+                    message: x.message(),
+                    url: x.url(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Post body to target, signed as `sha256=<hex>` like GitHub if `secrets` is set.
+async fn send(client: &reqwest::Client, webhook: &Webhook, body: &[u8]) -> anyhow::Result<()> {
+    let mut request = client
+        .post(webhook.url())
+        .header(CONTENT_TYPE, "application/json")
+        .header(
+            USER_AGENT,
+            format!("github-webhook-notification/{}", crate::SERVER_VERSION),
+        );
+    if let Some(secrets) = webhook.secrets() {
+        request = request.header(
+            webhook.signature_header(),
+            format!("sha256={}", hmac_sha256_hex(secrets, body)),
+        );
+    }
+    request
+        .headers(webhook.headers().clone())
+        .body(body.to_vec())
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Receivers are names of `[[webhook]]` targets.
+pub async fn process_webhook_message(
+    webhooks: HashMap<String, Webhook>,
+    mut rx: mpsc::Receiver<Command<String>>,
+) -> anyhow::Result<()> {
+    // Each target has its own timeout
+    let targets = webhooks
+        .iter()
+        .map(|(name, webhook)| Ok((name.as_str(), (webhook, http_client(webhook.timeout())?))))
+        .collect::<reqwest::Result<HashMap<&str, (&Webhook, reqwest::Client)>>>()?;
+    while let Some(cmd) = rx.recv().await {
+        match cmd {
+            Command::Bundle(bundle) => {
+                let body = serde_json::to_vec(&WebhookPayload::from(bundle.notification()))?;
+                for name in bundle.receiver() {
+                    let (webhook, client) = match targets.get(name.as_str()) {
+                        Some(target) => target,
+                        None => {
+                            error!("Webhook {:?} is not defined", name);
+                            continue;
+                        }
+                    };
+                    if let Err(e) = send(client, webhook, &body).await {
+                        error!("Got error in send webhook {:?} message {:?}", name, e);
+                    }
+                }
+            }
+            Command::Terminate => break,
+        }
+    }
+    debug!("webhook message daemon exiting...");
+    Ok(())
+}
@@ -0,0 +1,448 @@
+/*
+ ** Copyright (C) 2021 KunoiSayami
+ **
+ ** This program is free software: you can redistribute it and/or modify
+ ** it under the terms of the GNU Affero General Public License as published by
+ ** the Free Software Foundation, either version 3 of the License, or
+ ** any later version.
+ **
+ ** This program is distributed in the hope that it will be useful,
+ ** but WITHOUT ANY WARRANTY; without even the implied warranty of
+ ** MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ ** GNU Affero General Public License for more details.
+ **
+ ** You should have received a copy of the GNU Affero General Public License
+ ** along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::configure::Xmpp;
+use crate::datastructures::{escape_html, CommandBundle, Notification};
+use crate::sink::{
+    convert_html, html_to_plain, push_pending, tls_connector, wait_reconnect, Markup, Stream,
+    RECONNECT_DELAY_MAX, RECONNECT_DELAY_MIN,
+};
+use crate::Command;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use log::{debug, error, info, warn};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+use tokio::io::{AsyncWriteExt, BufReader, ReadHalf, WriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_rustls::rustls::pki_types::ServerName;
+
+const NS_TLS: &str = "urn:ietf:params:xml:ns:xmpp-tls";
+const NS_SASL: &str = "urn:ietf:params:xml:ns:xmpp-sasl";
+const NS_BIND: &str = "urn:ietf:params:xml:ns:xmpp-bind";
+const NS_STANZAS: &str = "urn:ietf:params:xml:ns:xmpp-stanzas";
+const NS_MUC: &str = "http://jabber.org/protocol/muc";
+const NS_XHTML_IM: &str = "http://jabber.org/protocol/xhtml-im";
+const NS_XHTML: &str = "http://www.w3.org/1999/xhtml";
+const RESOURCE: &str = "github-webhook-notification";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+// Whitespace keepalive, so idle connections are not dropped by NAT
+const KEEPALIVE: Duration = Duration::from_secs(60);
+
+type XmlReader = Reader<BufReader<ReadHalf<Box<dyn Stream>>>>;
+type XmlWriter = WriteHalf<Box<dyn Stream>>;
+
+/// Rooms are written like XMPP URIs, e.g. `room@conference.example.com?join`.
+pub fn room_of(address: &str) -> Option<&str> {
+    address.trim_start_matches("xmpp:").strip_suffix("?join")
+}
+
+/// Characters which are not allowed in XML 1.0.
+fn strip_control(s: &str) -> String {
+    s.chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\t'))
+        .collect()
+}
+
+/// Content of `<message>`, plain text with the link of event, and XHTML-IM if `xhtml`.
+pub fn build_body(notification: &Notification, xhtml: bool) -> String {
+    let html = strip_control(notification.text());
+    let mut text = html_to_plain(&html);
+    if let Some(url) = notification.url() {
+        text.push_str(&format!("\n{}", url));
+    }
+    let mut body = format!("<body>{}</body>", escape_html(&text));
+    if xhtml {
+        body.push_str(&format!(
+            "<html xmlns=\"{}\"><body xmlns=\"{}\"><p>{}</p></body></html>",
+            NS_XHTML_IM,
+            NS_XHTML,
+            convert_html(&html, Markup::Xhtml)
+        ));
+    }
+    body
+}
+
+/// Element of stream, only what is needed to follow negotiation and reply to server.
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn new(start: &BytesStart) -> anyhow::Result<Self> {
+        let mut attributes = Vec::new();
+        for attribute in start.attributes() {
+            let attribute = attribute?;
+            attributes.push((
+                String::from_utf8_lossy(attribute.key.as_ref()).to_string(),
+                attribute.unescape_value()?.to_string(),
+            ));
+        }
+        Ok(Self {
+            name: String::from_utf8_lossy(start.local_name().as_ref()).to_string(),
+            attributes,
+            ..Default::default()
+        })
+    }
+
+    fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|x| x.name == name)
+    }
+
+    fn expect(self, name: &str) -> anyhow::Result<Self> {
+        if self.name == name {
+            Ok(self)
+        } else {
+            Err(anyhow::anyhow!("Expect <{}> but got {:?}", name, self))
+        }
+    }
+}
+
+/// Read next child of stream, the header of stream is skipped.
+async fn read_element(reader: &mut XmlReader) -> anyhow::Result<Element> {
+    let mut buf = Vec::new();
+    let mut stack: Vec<Element> = Vec::new();
+    loop {
+        buf.clear();
+        let element = match reader.read_event_into_async(&mut buf).await? {
+            Event::Start(start) if stack.is_empty() && start.local_name().as_ref() == b"stream" => {
+                continue
+            }
+            Event::Start(start) => {
+                stack.push(Element::new(&start)?);
+                continue;
+            }
+            Event::Empty(start) => Element::new(&start)?,
+            Event::End(_) => stack
+                .pop()
+                .ok_or_else(|| anyhow::anyhow!("Stream closed by server"))?,
+            Event::Text(text) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&text.unescape()?);
+                }
+                continue;
+            }
+            Event::CData(text) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&String::from_utf8_lossy(&text));
+                }
+                continue;
+            }
+            Event::Eof => return Err(anyhow::anyhow!("Connection closed by server")),
+            _ => continue,
+        };
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(element),
+            None => return Ok(element),
+        }
+    }
+}
+
+async fn write(writer: &mut XmlWriter, s: &str) -> anyhow::Result<()> {
+    writer.write_all(s.as_bytes()).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Send a new stream header and read features, stream is restarted after TLS and SASL.
+async fn restart(
+    reader: &mut XmlReader,
+    writer: &mut XmlWriter,
+    xmpp: &Xmpp,
+) -> anyhow::Result<Element> {
+    write(
+        writer,
+        &format!(
+            concat!(
+                "<?xml version=\"1.0\"?><stream:stream to=\"{}\" version=\"1.0\" ",
+                "xmlns=\"jabber:client\" xmlns:stream=\"http://etherx.jabber.org/streams\">"
+            ),
+            escape_html(xmpp.domain())
+        ),
+    )
+    .await?;
+    read_element(reader).await?.expect("features")
+}
+
+async fn open(
+    stream: Box<dyn Stream>,
+    xmpp: &Xmpp,
+) -> anyhow::Result<(XmlReader, XmlWriter, Element)> {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = Reader::from_reader(BufReader::new(reader));
+    reader.check_end_names(false);
+    let features = restart(&mut reader, &mut writer, xmpp).await?;
+    Ok((reader, writer, features))
+}
+
+struct Connection {
+    writer: XmlWriter,
+    stanzas: mpsc::Receiver<anyhow::Result<Element>>,
+    reader: JoinHandle<()>,
+    jid: String,
+    joined: HashSet<String>,
+    id: u64,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+impl Connection {
+    /// Negotiate STARTTLS, authenticate by SASL PLAIN and bind resource.
+    async fn connect(xmpp: &Xmpp) -> anyhow::Result<Self> {
+        let tcp = TcpStream::connect((xmpp.server(), xmpp.port())).await?;
+        let (mut reader, mut writer, mut features) = open(Box::new(tcp), xmpp).await?;
+        if xmpp.starttls() {
+            if features.child("starttls").is_none() {
+                return Err(anyhow::anyhow!("STARTTLS is not offered by server"));
+            }
+            write(&mut writer, &format!("<starttls xmlns=\"{}\"/>", NS_TLS)).await?;
+            read_element(&mut reader).await?.expect("proceed")?;
+            let stream = reader.into_inner().into_inner().unsplit(writer);
+            let server_name = ServerName::try_from(xmpp.domain().to_string())?;
+            let stream = tls_connector()?.connect(server_name, stream).await?;
+            (reader, writer, features) = open(Box::new(stream), xmpp).await?;
+        }
+
+        let plain = features
+            .child("mechanisms")
+            .is_some_and(|x| x.children.iter().any(|x| x.text == "PLAIN"));
+        if !plain {
+            return Err(anyhow::anyhow!("SASL PLAIN is not offered by server"));
+        }
+        let credentials = STANDARD.encode(format!("\0{}\0{}", xmpp.username(), xmpp.password()));
+        write(
+            &mut writer,
+            &format!(
+                "<auth xmlns=\"{}\" mechanism=\"PLAIN\">{}</auth>",
+                NS_SASL, credentials
+            ),
+        )
+        .await?;
+        read_element(&mut reader)
+            .await?
+            .expect("success")
+            .map_err(|_| anyhow::anyhow!("Authentication failed"))?;
+
+        features = restart(&mut reader, &mut writer, xmpp).await?;
+        if features.child("bind").is_none() {
+            return Err(anyhow::anyhow!("Resource binding is not offered by server"));
+        }
+        write(
+            &mut writer,
+            &format!(
+                "<iq type=\"set\" id=\"bind\"><bind xmlns=\"{}\"><resource>{}</resource></bind></iq>",
+                NS_BIND, RESOURCE
+            ),
+        )
+        .await?;
+        let bound = read_element(&mut reader).await?.expect("iq")?;
+        let jid = match bound.attribute("type") {
+            Some("result") => bound
+                .child("bind")
+                .and_then(|x| x.child("jid"))
+                .map(|x| x.text.clone())
+                .unwrap_or_default(),
+            _ => return Err(anyhow::anyhow!("Unable bind resource: {:?}", bound)),
+        };
+        write(&mut writer, "<presence/>").await?;
+
+        // Read in another task, reading is not cancel safe in `select!`
+        let (tx, stanzas) = mpsc::channel(16);
+        let reader = tokio::spawn(async move {
+            loop {
+                let stanza = read_element(&mut reader).await;
+                let failed = stanza.is_err();
+                if tx.send(stanza).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            writer,
+            stanzas,
+            reader,
+            jid,
+            joined: HashSet::new(),
+            id: 0,
+        })
+    }
+
+    async fn write(&mut self, s: &str) -> anyhow::Result<()> {
+        write(&mut self.writer, s).await
+    }
+
+    async fn handle(&mut self, stanza: Element) -> anyhow::Result<()> {
+        let to = stanza
+            .attribute("from")
+            .map(|x| format!(" to=\"{}\"", escape_html(x)))
+            .unwrap_or_default();
+        match stanza.name.as_str() {
+            "iq" if matches!(stanza.attribute("type"), Some("get" | "set")) => {
+                let id = escape_html(stanza.attribute("id").unwrap_or_default());
+                let reply = if stanza.child("ping").is_some() {
+                    format!("<iq type=\"result\" id=\"{}\"{}/>", id, to)
+                } else {
+                    format!(
+                        concat!(
+                            "<iq type=\"error\" id=\"{}\"{}><error type=\"cancel\">",
+                            "<service-unavailable xmlns=\"{}\"/></error></iq>"
+                        ),
+                        id, to, NS_STANZAS
+                    )
+                };
+                self.write(&reply).await?;
+            }
+            // Join again before next message
+            "presence" if stanza.attribute("type") == Some("error") => {
+                let room = stanza
+                    .attribute("from")
+                    .and_then(|x| x.split('/').next())
+                    .unwrap_or_default();
+                warn!("Unable join room {}: {:?}", room, stanza.child("error"));
+                self.joined.remove(room);
+            }
+            "error" => return Err(anyhow::anyhow!("Stream error: {:?}", stanza)),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn join(&mut self, xmpp: &Xmpp, room: &str) -> anyhow::Result<()> {
+        if !self.joined.contains(room) {
+            self.write(&format!(
+                "<presence to=\"{}/{}\"><x xmlns=\"{}\"><history maxstanzas=\"0\"/></x></presence>",
+                escape_html(room),
+                escape_html(xmpp.nickname()),
+                NS_MUC
+            ))
+            .await?;
+            self.joined.insert(room.to_string());
+        }
+        Ok(())
+    }
+
+    async fn send_bundle(
+        &mut self,
+        xmpp: &Xmpp,
+        bundle: &CommandBundle<String>,
+    ) -> anyhow::Result<()> {
+        let body = build_body(bundle.notification(), xmpp.xhtml());
+        for address in bundle.receiver() {
+            let (to, kind) = match room_of(address) {
+                Some(room) => {
+                    self.join(xmpp, room).await?;
+                    (room, "groupchat")
+                }
+                None => (address.trim_start_matches("xmpp:"), "chat"),
+            };
+            self.id += 1;
+            self.write(&format!(
+                "<message to=\"{}\" type=\"{}\" id=\"notification-{}\">{}</message>",
+                escape_html(to),
+                kind,
+                self.id,
+                body
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Serve until terminated, messages are kept in `pending` until they are sent.
+async fn serve(
+    xmpp: &Xmpp,
+    addresses: &[String],
+    pending: &mut VecDeque<CommandBundle<String>>,
+    rx: &mut mpsc::Receiver<Command<String>>,
+) -> anyhow::Result<()> {
+    let mut connection = tokio::time::timeout(CONNECT_TIMEOUT, Connection::connect(xmpp)).await??;
+    info!("Connected to XMPP server as {}", connection.jid);
+    for room in addresses.iter().filter_map(|x| room_of(x)) {
+        connection.join(xmpp, room).await?;
+    }
+    let mut keepalive = tokio::time::interval_at(Instant::now() + KEEPALIVE, KEEPALIVE);
+    loop {
+        while let Some(bundle) = pending.front() {
+            connection.send_bundle(xmpp, bundle).await?;
+            pending.pop_front();
+        }
+        tokio::select! {
+            stanza = connection.stanzas.recv() => match stanza {
+                Some(stanza) => connection.handle(stanza?).await?,
+                None => return Err(anyhow::anyhow!("Connection closed by server")),
+            },
+            cmd = rx.recv() => match cmd {
+                Some(Command::Bundle(bundle)) => {
+                    push_pending(pending, bundle);
+                }
+                Some(Command::Terminate) | None => {
+                    connection.write("<presence type=\"unavailable\"/></stream:stream>").await.ok();
+                    return Ok(());
+                }
+            },
+            _ = keepalive.tick() => connection.write(" ").await?,
+        }
+    }
+}
+
+/// Keep a connection to XMPP server, rooms in `addresses` are joined once connected.
+pub async fn process_xmpp_message(
+    xmpp: Xmpp,
+    addresses: Vec<String>,
+    mut rx: mpsc::Receiver<Command<String>>,
+) -> anyhow::Result<()> {
+    let mut pending = VecDeque::new();
+    let mut delay = RECONNECT_DELAY_MIN;
+    loop {
+        let started = Instant::now();
+        let e = match serve(&xmpp, &addresses, &mut pending, &mut rx).await {
+            Ok(_) => break,
+            Err(e) => e,
+        };
+        if started.elapsed() > Duration::from_secs(RECONNECT_DELAY_MAX) {
+            delay = RECONNECT_DELAY_MIN;
+        }
+        error!("XMPP connection lost: {:?}, reconnect after {}s", e, delay);
+        if !wait_reconnect(Duration::from_secs(delay), &mut pending, &mut rx).await {
+            break;
+        }
+        delay = (delay * 2).min(RECONNECT_DELAY_MAX);
+    }
+    debug!("XMPP message daemon exiting...");
+    Ok(())
+}
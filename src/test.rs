@@ -16,10 +16,58 @@
  */
 
 #[allow(dead_code)]
+#[allow(clippy::module_inception)]
 #[cfg(test)]
 mod test {
-    use crate::configure::Config;
-    use crate::{DisplayableEvent, GitHubEarlyParse, GitHubPingEvent, GitHubPushEvent};
+    use crate::bitbucket::{BitbucketEvent, BitbucketProvider};
+    use crate::configure::{
+        ActionsNotify, Config, Gotify, Irc, Matrix, Ntfy, ProviderKind, RepositoryBuilder,
+        SinkKind, Smtp, SmtpSecurity, Teams, TomlConfig, TomlGotify, TomlIrc, TomlMatrix, TomlNtfy,
+        TomlSmtp, TomlWebhook, TomlXmpp, Webhook, Xmpp,
+    };
+    use crate::datastructures::{
+        CheckEvent, CommandBundle, DisplayableEvent, EventKind, GitHubCheckSuiteEvent,
+        GitHubCodeScanningAlertEvent, GitHubCommitCommentEvent, GitHubDependabotAlertEvent,
+        GitHubDeploymentEvent, GitHubDeploymentStatusEvent, GitHubDiscussionCommentEvent,
+        GitHubDiscussionEvent, GitHubEarlyParse, GitHubForkEvent, GitHubGenericEvent,
+        GitHubGollumEvent, GitHubIssueCommentEvent, GitHubIssuesEvent, GitHubMemberEvent,
+        GitHubPingEvent, GitHubPullRequestEvent, GitHubPullRequestReviewCommentEvent,
+        GitHubPullRequestReviewEvent, GitHubPushEvent, GitHubReleaseEvent, GitHubRepositoryEvent,
+        GitHubRepositoryVulnerabilityAlertEvent, GitHubSecretScanningAlertEvent, GitHubStarEvent,
+        GitHubStatusEvent, GitHubWorkflowJobEvent, GitHubWorkflowRunEvent, Notification, PushKind,
+    };
+    use crate::gitlab::{
+        GitLabMergeRequestEvent, GitLabPipelineEvent, GitLabProvider, GitLabPushEvent,
+    };
+    use crate::markdown::markdown_to_telegram_html;
+    use crate::provider::{GitHubProvider, NormalizedEvent, WebhookProvider};
+    use crate::sink::discord::process_discord_message;
+    use crate::sink::email::process_email_message;
+    use crate::sink::gotify::process_gotify_message;
+    use crate::sink::irc::{build_lines, process_irc_message};
+    use crate::sink::matrix::process_matrix_message;
+    use crate::sink::ntfy::process_ntfy_message;
+    use crate::sink::slack::{build_payload as slack_payload, process_slack_message};
+    use crate::sink::teams::process_teams_message;
+    use crate::sink::webhook::{process_webhook_message, WebhookPayload};
+    use crate::sink::xmpp::process_xmpp_message;
+    use crate::sink::{
+        convert_html, html_to_plain, post_json, process_json_webhook, push_pending, Markup,
+    };
+    use crate::{
+        forward_check_event, forward_security_event, route_post, route_webhook, CheckState,
+        Command, ExtraData, Sinks,
+    };
+    use axum::body::{Body, HttpBody};
+    use axum::http::{HeaderMap, Request as HttpRequest};
+    use axum::response::IntoResponse;
+    use axum::Extension;
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::Arc;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::sync::{mpsc, RwLock};
     use walkdir::WalkDir;
 
     #[test]
@@ -29,12 +77,21 @@ mod test {
         assert_eq!(cfg.server().secrets(), "1145141919810");
         assert!(cfg.server().token().is_empty());
         assert_eq!(cfg.telegram().bot_token(), "1145141919:810abcdefg");
+        assert_eq!(
+            cfg.matrix().as_ref().unwrap().homeserver(),
+            "https://matrix-client.matrix.org"
+        );
+        assert_eq!(cfg.matrix().as_ref().unwrap().timeout(), 10);
+        let smtp = cfg.smtp().as_ref().unwrap();
+        assert_eq!(smtp.security(), SmtpSecurity::StartTls);
+        assert_eq!(smtp.port(), 587);
+        assert_eq!(smtp.digest_interval(), Some(30));
         let result = vec![114514, 1919810i64];
         assert_eq!(cfg.telegram().send_to().len(), result.len());
         assert_eq!(
             cfg.telegram()
                 .send_to()
-                .into_iter()
+                .iter()
                 .zip(&result)
                 .filter(|&(a, b)| a == b)
                 .count(),
@@ -47,13 +104,60 @@ mod test {
         assert!(repo.branch_ignore().is_empty());
         assert!(!repo.send_to().is_empty());
         assert_eq!(repo.send_to().len(), 6);
+        assert_eq!(repo.security_send_to(), &vec![-1001145141919i64]);
 
         // Test second repository
         let repo = cfg.fetch_repository_configure("BillyKing/Wrestling");
         assert!(!repo.is_default());
         assert_eq!(repo.send_to().len(), 1);
+        assert_eq!(repo.security_send_to(), repo.send_to());
+        assert!(repo.is_deployment_environment_enabled("production"));
+        assert!(!repo.is_deployment_environment_enabled("staging"));
+        assert_eq!(
+            repo.extra_events(),
+            &vec!["star".to_string(), "fork".to_string()]
+        );
         assert_eq!(repo.branch_ignore().len(), 2);
+        assert_eq!(repo.pull_request_actions(), &vec!["merged".to_string()]);
+        assert!(repo.notify_branch_delete());
+        assert!(!repo.notify_force_push());
         assert_eq!(repo.secrets(), cfg.server().secrets());
+        assert_eq!(repo.sink_send_to(SinkKind::Discord).len(), 1);
+        assert_eq!(repo.sink_send_to(SinkKind::Slack).len(), 1);
+        assert_eq!(repo.sink_send_to(SinkKind::Matrix).len(), 2);
+        assert_eq!(
+            repo.sink_send_to(SinkKind::Email),
+            &["billy@example.com".to_string()]
+        );
+        assert_eq!(repo.sink_send_to(SinkKind::Teams).len(), 1);
+        assert_eq!(repo.sink_send_to(SinkKind::Webhook), &["ci".to_string()]);
+        assert_eq!(repo.sink_send_to(SinkKind::Ntfy).len(), 2);
+        assert_eq!(
+            repo.sink_send_to(SinkKind::Gotify),
+            &["A1145141919".to_string()]
+        );
+        assert!(cfg.is_sink_used(SinkKind::Discord));
+        assert_eq!(cfg.ntfy().server(), "https://ntfy.example.com");
+        assert_eq!(cfg.ntfy().timeout(), 10);
+        assert_eq!(cfg.teams().timeout(), 60);
+        let irc = cfg.irc().as_ref().unwrap();
+        assert!(irc.tls());
+        assert_eq!(irc.port(), 6697);
+        assert_eq!(irc.username(), "wrestling-bot");
+        assert_eq!(cfg.irc_channels(), vec!["#wrestling".to_string()]);
+        let xmpp = cfg.xmpp().as_ref().unwrap();
+        assert_eq!(xmpp.server(), "example.com");
+        assert_eq!(xmpp.nickname(), "webhook");
+        assert_eq!(cfg.xmpp_addresses().len(), 2);
+        assert_eq!(
+            cfg.gotify().as_ref().unwrap().server(),
+            "https://gotify.example.com"
+        );
+        assert_eq!(cfg.gotify().as_ref().unwrap().timeout(), 10);
+        let webhook = &cfg.webhooks()["ci"];
+        assert_eq!(webhook.signature_header(), "X-Webhook-Signature-256");
+        assert_eq!(webhook.timeout(), 10);
+        assert_eq!(webhook.headers().len(), 1);
 
         // Test third repository
         let repo = cfg.fetch_repository_configure("sample/test");
@@ -61,19 +165,166 @@ mod test {
         assert!(repo.branch_ignore().is_empty());
         assert_eq!(
             repo.send_to()
-                .into_iter()
+                .iter()
                 .zip(&result)
                 .filter(|(a, b)| a == b)
                 .count(),
             result.len()
         );
         assert_eq!(repo.secrets(), "2333");
+        assert_eq!(repo.comment_length(), 20);
+        assert_eq!(repo.actions_notify(), ActionsNotify::Changed);
+        assert!(repo.notify_tag_push());
+        assert!(repo.forward_unknown_events());
+        assert_eq!(repo.pull_request_actions().len(), 5);
 
         // Test not exist repository
         let r_missing = cfg.fetch_repository_configure("114514/1919810");
         assert_eq!(r_missing.secrets(), cfg.server().secrets());
         assert!(r_missing.branch_ignore().is_empty());
         assert_eq!(r_missing.send_to(), cfg.telegram().send_to());
+        assert_eq!(r_missing.security_send_to(), cfg.telegram().send_to());
+        assert!(!r_missing.extra_events().contains(&"star".to_string()));
+        assert!(r_missing.extra_events().contains(&"member".to_string()));
+        assert_eq!(r_missing.discussion_actions().len(), 3);
+        assert!(r_missing.is_deployment_environment_enabled("staging"));
+        assert!(!r_missing.notify_tag_push());
+        assert_eq!(r_missing.actions_notify(), ActionsNotify::Failure);
+        assert!(!r_missing.notify_branch_create());
+        assert!(!r_missing.notify_branch_delete());
+        assert!(r_missing.notify_force_push());
+        assert!(!r_missing.forward_unknown_events());
+
+        // Test endpoints
+        assert_eq!(cfg.endpoints().len(), 1);
+        let endpoint = &cfg.endpoints()[0];
+        assert_eq!(endpoint.path(), "/gitea/home");
+        assert_eq!(endpoint.provider(), Some(ProviderKind::Gitea));
+        let mut repo = cfg.fetch_repository_configure("BillyKing/Wrestling");
+        repo.set_default_secrets(endpoint.secrets().as_deref().unwrap());
+        assert_eq!(repo.secrets(), "114514");
+        let mut repo = cfg.fetch_repository_configure("sample/test");
+        repo.set_default_secrets(endpoint.secrets().as_deref().unwrap());
+        assert_eq!(repo.secrets(), "2333");
+    }
+
+    #[test]
+    fn test_configure_invalid() {
+        let base = concat!(
+            "[server]\nbind = \"127.0.0.1\"\nport = 11451\n",
+            "[telegram]\nbot_token = \"1145141919:810abcdefg\"\nsend_to = 114514\n",
+        );
+        let parse = |extra: &str| {
+            let config = TomlConfig::try_from(format!("{}{}", base, extra).as_str()).unwrap();
+            Config::try_from(&config)
+        };
+        assert!(parse("").is_ok());
+
+        let err = parse("[[repository]]\nfull_name = \"a/b\"\nactions_notify = \"always\"\n")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("actions_notify"));
+
+        let err = parse("[[endpoint]]\npath = \"/svn\"\nprovider = \"svn\"\n")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("provider"));
+
+        for path in ["/", "gitlab"] {
+            let err = parse(&format!(
+                "[[endpoint]]\npath = \"{}\"\nprovider = \"gitlab\"\n",
+                path
+            ))
+            .err()
+            .unwrap();
+            assert!(err.to_string().contains("endpoint path"));
+        }
+
+        let err = parse(concat!(
+            "[[endpoint]]\npath = \"/forge\"\nprovider = \"gitlab\"\n",
+            "[[endpoint]]\npath = \"/forge\"\nprovider = \"gitea\"\n",
+        ))
+        .err()
+        .unwrap();
+        assert!(err
+            .to_string()
+            .contains("Duplicate endpoint path \"/forge\""));
+
+        let err = parse(concat!(
+            "[smtp]\nhost = \"smtp.example.com\"\nsecurity = \"ssl\"\n",
+            "from = \"bot@example.com\"\n",
+        ))
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("security"));
+
+        let err = parse("[smtp]\nhost = \"smtp.example.com\"\nfrom = \"not an address\"\n")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("smtp from"));
+
+        let err = parse("[smtp]\nhost = \"smtp example\"\nfrom = \"bot@example.com\"\n")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("smtp host"));
+
+        let err = parse(concat!(
+            "[smtp]\nhost = \"127.0.0.1\"\nsecurity = \"none\"\nusername = \"bot\"\n",
+            "from = \"bot@example.com\"\n",
+        ))
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("allow_insecure_auth"));
+
+        let err = parse("[xmpp]\njid = \"bot@example.com\"\npassword = \"1\"\nstarttls = false\n")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("allow_insecure_auth"));
+
+        for jid in ["example.com", "@example.com", "bot@"] {
+            let err = parse(&format!("[xmpp]\njid = \"{}\"\npassword = \"1\"\n", jid))
+                .err()
+                .unwrap();
+            assert!(err.to_string().contains("xmpp jid"));
+        }
+        assert!(parse(concat!(
+            "[smtp]\nhost = \"127.0.0.1\"\nsecurity = \"none\"\nusername = \"bot\"\n",
+            "from = \"bot@example.com\"\nallow_insecure_auth = true\n",
+        ))
+        .is_ok());
+
+        let err = parse(concat!(
+            "[smtp]\nhost = \"smtp.example.com\"\nfrom = \"bot@example.com\"\n",
+            "digest_interval = 9223372036854775807\n",
+        ))
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("digest_interval"));
+
+        let err = parse("[[repository]]\nfull_name = \"a/b\"\nemail_send_to = [\"billy\"]\n")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("email_send_to \"billy\""));
+
+        for header in ["\"Bad Header\" = \"1\"", "Authorization = \"Bearer\\n1\""] {
+            let err = parse(&format!(
+                "[[webhook]]\nname = \"ci\"\nurl = \"https://example.com\"\n[webhook.headers]\n{}\n",
+                header
+            ))
+            .err()
+            .unwrap();
+            assert!(err.to_string().contains("of webhook \"ci\""));
+        }
+
+        for homeserver in ["matrix.org", "mailto:admin@matrix.org", "https://"] {
+            let err = parse(&format!(
+                "[matrix]\nhomeserver = \"{}\"\naccess_token = \"syt_token\"\n",
+                homeserver
+            ))
+            .err()
+            .unwrap();
+            assert!(err.to_string().contains("matrix homeserver"));
+        }
     }
 
     /*// src: https://docs.rs/actix-web/4.0.0-beta.14/actix_web/test/struct.TestRequest.html
@@ -108,6 +359,1577 @@ mod test {
         assert_eq!(event.branch_name(), "master");
     }
 
+    #[test]
+    fn test_parse_pull_request() {
+        let s = std::fs::read_to_string("example/pull_request.json").unwrap();
+        let event: GitHubPullRequestEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(event.get_full_name(), "MagomeYae/test-action");
+        assert_eq!(event.branch_name(), "master");
+        assert_eq!(event.action(), "closed");
+        assert_eq!(event.action_kind(), "merged");
+        let text = event.to_string();
+        assert!(text.contains("merged"));
+        assert!(text.contains("Bump &lt;serde&gt; &amp; friends"));
+        assert!(text.contains("<code>KunoiSayami:bump-serde</code> → <code>master</code>"));
+
+        let mut value: serde_json::Value = serde_json::from_str(s.as_str()).unwrap();
+        value["pull_request"]["head"]["label"] = serde_json::Value::from("Billy:<fix>&co");
+        value["pull_request"]["base"]["ref"] = serde_json::Value::from("release<1>");
+        let event: GitHubPullRequestEvent = serde_json::from_value(value).unwrap();
+        assert!(event
+            .to_string()
+            .contains("<code>Billy:&lt;fix&gt;&amp;co</code> → <code>release&lt;1&gt;</code>"));
+    }
+
+    #[test]
+    fn test_parse_pull_request_review() {
+        let s = std::fs::read_to_string("example/pull_request_review.json").unwrap();
+        let event: GitHubPullRequestReviewEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(event.branch_name(), "master");
+        let text = event.to_string();
+        assert!(text.starts_with("✅ <b>MagomeYae</b> approved <a href=\"https://github.com/MagomeYae/test-action/pull/2#pullrequestreview-1155712466\">pull request #2</a>"));
+        assert!(text.ends_with("\n\nLooks good, thanks!"));
+
+        let s = std::fs::read_to_string("example/pull_request_review_comment.json").unwrap();
+        let mut event: GitHubPullRequestReviewCommentEvent =
+            serde_json::from_str(s.as_str()).unwrap();
+        event.set_comment_length(8);
+        let text = event.to_string();
+        assert!(text.contains("<code>Cargo.toml:17</code>"));
+        assert!(text.ends_with("Why pin …"));
+    }
+
+    #[test]
+    fn test_parse_issues() {
+        let s = std::fs::read_to_string("example/issues.json").unwrap();
+        let event: GitHubIssuesEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(event.get_full_name(), "MagomeYae/test-action");
+        assert_eq!(event.action(), "labeled");
+        assert!(event.to_string().contains("<code>bug</code>"));
+    }
+
+    #[test]
+    fn test_parse_issue_comment() {
+        let s = std::fs::read_to_string("example/issue_comment.json").unwrap();
+        let mut event: GitHubIssueCommentEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert!(event.issue().is_pull_request());
+        let text = event.to_string();
+        assert!(text.contains("pull request #2"));
+        assert!(text.contains("please check &lt;Cargo.lock&gt; before merge"));
+        event.set_comment_length(4);
+        assert!(event.to_string().ends_with("LGTM…"));
+    }
+
+    #[test]
+    fn test_parse_push_tag() {
+        let s = std::fs::read_to_string("example/push_tag.json").unwrap();
+        let event: GitHubPushEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert!(event.created());
+        assert_eq!(event.tag_name(), Some("v3.1.0-rc1"));
+        assert!(event.to_string().contains("tag v3.1.0-rc1</a>"));
+    }
+
+    #[test]
+    fn test_push_kind() {
+        let s = std::fs::read_to_string("example/push.json").unwrap();
+        let origin: serde_json::Value = serde_json::from_str(s.as_str()).unwrap();
+        let event: GitHubPushEvent = serde_json::from_value(origin.clone()).unwrap();
+        assert_eq!(event.kind(), PushKind::Commits);
+
+        let mut value = origin.clone();
+        value["forced"] = serde_json::Value::Bool(true);
+        let event: GitHubPushEvent = serde_json::from_value(value).unwrap();
+        assert_eq!(event.kind(), PushKind::ForcePushed);
+        assert!(event
+            .to_string()
+            .contains("force-pushed</a> <b>to MagomeYae/test-action:master</b> (<code>e2b3669c..e9889e9a</code>)"));
+
+        let mut value = origin.clone();
+        value["after"] = serde_json::Value::from("0000000000000000000000000000000000000000");
+        value["commits"] = serde_json::Value::Array(vec![]);
+        let event: GitHubPushEvent = serde_json::from_value(value).unwrap();
+        assert_eq!(event.kind(), PushKind::BranchDeleted);
+        assert!(event.to_string().contains("deleted branch"));
+
+        let mut value = origin.clone();
+        value["forced"] = serde_json::Value::Bool(true);
+        value["before"] = serde_json::Value::from("e2b3");
+        value["after"] = serde_json::Value::from("ééééééééé");
+        let event: GitHubPushEvent = serde_json::from_value(value).unwrap();
+        assert!(event.to_string().contains("(<code>e2b3..éééééééé</code>)"));
+
+        let mut value = origin;
+        value["before"] = serde_json::Value::from("0000000000000000000000000000000000000000");
+        let event: GitHubPushEvent = serde_json::from_value(value).unwrap();
+        assert_eq!(event.kind(), PushKind::BranchCreated);
+        assert!(event.to_string().contains("created branch"));
+    }
+
+    #[test]
+    fn test_parse_release() {
+        let s = std::fs::read_to_string("example/release.json").unwrap();
+        let event: GitHubReleaseEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(event.branch_name(), "master");
+        assert!(event.release().prerelease());
+        let text = event.to_string();
+        assert!(text.contains("pre-release Release candidate 1</a>"));
+        assert!(text.contains("<code>v3.1.0-rc1</code>"));
+        assert!(text.contains("<b>What's Changed</b>"));
+        assert!(text.contains("github-webhook-notification_linux_amd64</a> (5120.0 KiB)"));
+    }
+
+    #[test]
+    fn test_parse_check_events() {
+        let s = std::fs::read_to_string("example/workflow_run.json").unwrap();
+        let event: GitHubWorkflowRunEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(event.conclusion(), Some("failure"));
+        assert_eq!(
+            event.state_key(),
+            "MagomeYae/test-action/workflow/Build binary/master"
+        );
+        let text = event.to_string();
+        assert!(text.starts_with("❌ <a href=\"https://github.com/MagomeYae/test-action/actions/runs/3326571836\">Workflow Build binary #42</a> failure"));
+        assert!(text.contains("<code>e9889e9a</code></a>: Support pull_request events\n"));
+        assert!(text.ends_with("Triggered by <b>KunoiSayami</b>"));
+
+        let s = std::fs::read_to_string("example/workflow_job.json").unwrap();
+        let event: GitHubWorkflowJobEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert!(event
+            .to_string()
+            .contains("Job Build (ubuntu-latest) of workflow Build binary</a> timed out"));
+
+        let s = std::fs::read_to_string("example/check_suite.json").unwrap();
+        let event: GitHubCheckSuiteEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(event.branch_name(), "master");
+        assert!(event.to_string().contains(
+            "https://github.com/MagomeYae/test-action/commit/e9889e9a3089ea7df3c8df4685b853052633e1d6/checks"
+        ));
+
+        // Jobs and check suites repeat the workflow run, so they are opt-in
+        let settings = RepositoryBuilder::new().build();
+        let body = std::fs::read("example/workflow_job.json").unwrap();
+        let response = GitHubProvider
+            .parse("workflow_job", &body, &settings)
+            .err()
+            .unwrap();
+        assert_eq!(response.status(), 204);
+        let body = std::fs::read("example/check_suite.json").unwrap();
+        assert!(GitHubProvider
+            .parse("check_suite", &body, &settings)
+            .is_err());
+        let settings = RepositoryBuilder::new()
+            .set_extra_events(vec!["check_suite".to_string()])
+            .build();
+        assert!(matches!(
+            GitHubProvider
+                .parse("check_suite", &body, &settings)
+                .unwrap()
+                .as_slice(),
+            [NormalizedEvent::Check(_)]
+        ));
+    }
+
+    #[test]
+    fn test_check_state() {
+        let mut state = CheckState::default();
+        assert!(state.insert("0".to_string()));
+        assert!(!state.insert("0".to_string()));
+        for i in 1..4096 {
+            state.insert(i.to_string());
+        }
+        // The oldest one is forgotten instead of all of them
+        assert!(state.insert("4096".to_string()));
+        assert!(state.insert("0".to_string()));
+        assert!(!state.insert("2".to_string()));
+        assert!(state.remove("2"));
+        assert!(!state.remove("2"));
+        assert!(!state.remove("1"));
+        assert_eq!(state.keys.len(), state.order.len());
+    }
+
+    #[test]
+    fn test_parse_security_alerts() {
+        let s = std::fs::read_to_string("example/dependabot_alert.json").unwrap();
+        let event: GitHubDependabotAlertEvent = serde_json::from_str(s.as_str()).unwrap();
+        let text = event.to_string();
+        assert!(text.contains("Dependabot alert #7</a> created"));
+        assert!(text.contains("Severity: 🟧 <b>high</b> (<code>GHSA-fg7r-2g4j-5cgr</code>)"));
+        assert!(text.contains("<code>cargo/tokio</code> <code>&gt;= 1.8.0, &lt; 1.8.4</code>"));
+        assert!(text.contains("Manifest: <code>Cargo.lock</code>"));
+        assert!(text.ends_with("Patched in: <code>1.8.4</code>"));
+
+        let s = std::fs::read_to_string("example/code_scanning_alert.json").unwrap();
+        let event: GitHubCodeScanningAlertEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(event.branch_name(), "master");
+        let text = event.to_string();
+        assert!(text.contains("Code scanning alert #3</a> appeared in branch"));
+        assert!(text.contains("Severity: 🟧 <b>high</b>"));
+        assert!(text.ends_with("Location: <code>src/main.rs:42</code>"));
+
+        let s = std::fs::read_to_string("example/secret_scanning_alert.json").unwrap();
+        let event: GitHubSecretScanningAlertEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert!(event
+            .to_string()
+            .ends_with("Secret type: <b>Telegram Bot Token</b>"));
+
+        let s = std::fs::read_to_string("example/repository_vulnerability_alert.json").unwrap();
+        let event: GitHubRepositoryVulnerabilityAlertEvent =
+            serde_json::from_str(s.as_str()).unwrap();
+        let text = event.to_string();
+        assert!(text.contains("<code>tower-http</code> <code>&lt; 0.2.2</code>"));
+        assert!(text.ends_with("Patched in: <code>0.2.2</code>"));
+    }
+
+    #[test]
+    fn test_parse_repository_events() {
+        let s = std::fs::read_to_string("example/star.json").unwrap();
+        let event: GitHubStarEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert!(event.to_string().ends_with("(42 stars in total)"));
+
+        let s = std::fs::read_to_string("example/fork.json").unwrap();
+        let event: GitHubForkEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(event.get_full_name(), "MagomeYae/test-action");
+        assert!(event.to_string().contains("to <a href=\"https://github.com/KunoiSayami/test-action\">KunoiSayami/test-action</a> (3 forks in total)"));
+
+        let s = std::fs::read_to_string("example/member.json").unwrap();
+        let event: GitHubMemberEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert!(event.to_string().contains(
+            "added collaborator <a href=\"https://github.com/KunoiSayami\">KunoiSayami</a> to"
+        ));
+
+        let s = std::fs::read_to_string("example/repository.json").unwrap();
+        let event: GitHubRepositoryEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(event.renamed_from(), Some("test-action"));
+        assert!(event
+            .to_string()
+            .ends_with("MagomeYae/webhook-test</a> (was <code>test-action</code>)"));
+    }
+
+    #[test]
+    fn test_parse_discussion() {
+        let s = std::fs::read_to_string("example/discussion.json").unwrap();
+        let event: GitHubDiscussionEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(event.action(), "answered");
+        let text = event.to_string();
+        assert!(
+            text.contains("<b>How to forward &lt;issues&gt; only?</b>\nCategory: <b>Q&amp;A</b>")
+        );
+        assert!(text.ends_with("\">Answer</a> by <b>MagomeYae</b>"));
+
+        let mut value: serde_json::Value = serde_json::from_str(s.as_str()).unwrap();
+        value["action"] = serde_json::Value::from("category_changed");
+        value["answer"] = serde_json::Value::Null;
+        value["changes"] = serde_json::json!({"category": {"from": {"name": "General"}}});
+        let event: GitHubDiscussionEvent = serde_json::from_value(value).unwrap();
+        assert!(event
+            .to_string()
+            .ends_with("Category: <b>Q&amp;A</b> (was General)"));
+
+        let s = std::fs::read_to_string("example/discussion_comment.json").unwrap();
+        let event: GitHubDiscussionCommentEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert!(event
+            .to_string()
+            .starts_with("💬 <b>MagomeYae</b> commented on"));
+    }
+
+    #[test]
+    fn test_parse_deployment() {
+        let s = std::fs::read_to_string("example/deployment.json").unwrap();
+        let event: GitHubDeploymentEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(event.branch_name(), "master");
+        assert!(event.to_string().contains(
+            "deployment</a> of <code>e9889e9a</code> (<code>master</code>) to <b>production</b>"
+        ));
+
+        let s = std::fs::read_to_string("example/deployment_status.json").unwrap();
+        let event: GitHubDeploymentStatusEvent = serde_json::from_str(s.as_str()).unwrap();
+        let text = event.to_string();
+        assert!(text.starts_with("❌ Deploy of <code>e9889e9a</code> (<code>master</code>) to <b>production</b>: <b>failure</b>"));
+        assert!(text.contains("Target: <a href=\"https://webhook.example.com\">"));
+        assert!(text.contains("<a href=\"https://github.com/MagomeYae/test-action/actions/runs/3348831720\">View logs</a>"));
+        assert!(text.ends_with("Health check failed"));
+    }
+
+    #[test]
+    fn test_parse_commit_comment_gollum_status() {
+        let s = std::fs::read_to_string("example/commit_comment.json").unwrap();
+        let mut event: GitHubCommitCommentEvent = serde_json::from_str(s.as_str()).unwrap();
+        event.set_comment_length(10);
+        let text = event.to_string();
+        assert!(text.contains(">commit e9889e9a</a> <b>in MagomeYae/test-action</b>"));
+        assert!(text.contains("<code>src/main.rs:12</code>"));
+        assert!(text.ends_with("Should thi…"));
+
+        let s = std::fs::read_to_string("example/gollum.json").unwrap();
+        let event: GitHubGollumEvent = serde_json::from_str(s.as_str()).unwrap();
+        let text = event.to_string();
+        assert!(text.starts_with("📖 <b>MagomeYae</b> updated 2 wiki pages"));
+        assert!(text.ends_with("\">Deploy &lt;notes&gt;</a>"));
+
+        let s = std::fs::read_to_string("example/status.json").unwrap();
+        let event: GitHubStatusEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(event.branch_name(), "master");
+        assert_eq!(CheckEvent::action(&event), "completed");
+        assert_eq!(event.conclusion(), Some("failure"));
+        let text = event.to_string();
+        assert!(text.starts_with(
+            "❌ <a href=\"https://ci.example.com/builds/1145\">Status ci/jenkins</a> failure"
+        ));
+        assert!(text.ends_with("Build failed in 3m 12s"));
+
+        let mut value: serde_json::Value = serde_json::from_str(s.as_str()).unwrap();
+        value["target_url"] = serde_json::Value::from("");
+        let event: GitHubStatusEvent = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            event.event_url().as_deref(),
+            Some(event.commit().html_url())
+        );
+        assert!(event
+            .to_string()
+            .contains(&format!("<a href=\"{}\">", event.commit().html_url())));
+    }
+
+    #[test]
+    fn test_parse_generic_event() {
+        let s = std::fs::read_to_string("example/github_package_event.json").unwrap();
+        let mut event: GitHubGenericEvent = serde_json::from_str(s.as_str()).unwrap();
+        event.set_event("package");
+        assert_eq!(
+            event.to_string(),
+            "📨 <b>package</b> (published) <b>in MagomeYae/test-action</b> by <b>MagomeYae</b>\n\n\
+            <a href=\"https://github.com/MagomeYae/test-action/pkgs/container/test-action\">View on GitHub</a>"
+        );
+
+        let mut value: serde_json::Value = serde_json::from_str(s.as_str()).unwrap();
+        value["package"]["html_url"] = serde_json::Value::from("https://example.com/\"><b>");
+        let mut event: GitHubGenericEvent = serde_json::from_value(value).unwrap();
+        event.set_event("package");
+        assert!(event
+            .to_string()
+            .ends_with("<a href=\"https://example.com/&quot;&gt;&lt;b&gt;\">View on GitHub</a>"));
+        event.set_event("registry_package");
+        assert_eq!(
+            event.html_url(),
+            Some("https://github.com/MagomeYae/test-action")
+        );
+
+        let mut value: serde_json::Value = serde_json::from_str(s.as_str()).unwrap();
+        value["package"]["html_url"] = serde_json::Value::from("");
+        let mut event: GitHubGenericEvent = serde_json::from_value(value).unwrap();
+        event.set_event("package");
+        assert_eq!(
+            event.html_url(),
+            Some("https://github.com/MagomeYae/test-action")
+        );
+    }
+
+    #[test]
+    fn test_parse_gitlab() {
+        let s = std::fs::read_to_string("example/gitlab_push.json").unwrap();
+        let event: GitLabPushEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(event.get_full_name(), "BillyKing/Wrestling");
+        assert_eq!(event.kind(), PushKind::Commits);
+        assert_eq!(event.branch_name(), "master");
+        assert!(event.to_string().starts_with(
+            "🔨 <a href=\"https://gitlab.example.com/BillyKing/Wrestling/-/compare/95790bf891e76fee5e1747ab589903a6a1f80f22...da1560886d4f094c3e6c9ef40349f7d38b5d27d7\">2 new commits</a> <b>to BillyKing/Wrestling:master</b>"
+        ));
+
+        let s = std::fs::read_to_string("example/gitlab_tag_push.json").unwrap();
+        let event: GitLabPushEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(event.kind(), PushKind::Tag);
+        assert!(event.created());
+        assert_eq!(event.branch_name(), "");
+
+        let s = std::fs::read_to_string("example/gitlab_merge_request.json").unwrap();
+        let event: GitLabMergeRequestEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(event.action_kind(), "merged");
+        assert!(event.to_string().contains(
+            "<b>MS-Viewport &lt;fix&gt;</b>\n<code>ms-viewport</code> → <code>master</code>"
+        ));
+
+        let s = std::fs::read_to_string("example/gitlab_pipeline.json").unwrap();
+        let event: GitLabPipelineEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(CheckEvent::action(&event), "completed");
+        assert_eq!(event.conclusion(), Some("failure"));
+        assert!(event.to_string().starts_with(
+            "❌ <a href=\"https://gitlab.example.com/BillyKing/Wrestling/-/pipelines/31\">Pipeline #31</a> failure <b>on BillyKing/Wrestling:master</b>"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_security_event_skip_sinks() {
+        let (bot_tx, mut bot_rx) = mpsc::channel(16);
+        let (discord_tx, mut discord_rx) = mpsc::channel(16);
+        let mut sinks = Sinks::default();
+        sinks.insert(SinkKind::Discord, discord_tx);
+        let data = Arc::new(RwLock::new(ExtraData {
+            bot_tx,
+            sinks,
+            check_state: CheckState::default(),
+        }));
+        let settings = RepositoryBuilder::new()
+            .set_send_to(vec![233])
+            .set_security_send_to(vec![-1001145141919])
+            .set_sink_send_to(HashMap::from([(
+                SinkKind::Discord,
+                vec!["https://discord.com/api/webhooks/114514/1919810".to_string()],
+            )]))
+            .build();
+        let s = std::fs::read_to_string("example/dependabot_alert.json").unwrap();
+        let event: GitHubDependabotAlertEvent = serde_json::from_str(s.as_str()).unwrap();
+
+        let response = forward_security_event(&data, &settings, &event).await;
+        assert_eq!(response.status(), 200);
+        match bot_rx.try_recv() {
+            Ok(Command::Bundle(bundle)) => assert_eq!(bundle.receiver(), &vec![-1001145141919]),
+            _ => unreachable!(),
+        }
+        assert!(discord_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_route_gitlab() {
+        let cfg = Config::new("example/sample.toml").unwrap();
+        let (bot_tx, mut bot_rx) = mpsc::channel(16);
+        let (discord_tx, mut discord_rx) = mpsc::channel(16);
+        let mut sinks = Sinks::default();
+        sinks.insert(SinkKind::Discord, discord_tx);
+        let data = Arc::new(RwLock::new(ExtraData {
+            bot_tx,
+            sinks,
+            check_state: CheckState::default(),
+        }));
+        let body = std::fs::read("example/gitlab_merge_request.json").unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Gitlab-Event", "Merge Request Hook".parse().unwrap());
+
+        let response = route_webhook(&GitLabProvider, None, &headers, &body, &cfg, &data).await;
+        assert_eq!(response.status(), 403);
+
+        headers.insert("X-Gitlab-Token", "1145141919810".parse().unwrap());
+        let response = route_webhook(&GitLabProvider, None, &headers, &body, &cfg, &data).await;
+        assert_eq!(response.status(), 200);
+        match bot_rx.try_recv() {
+            Ok(Command::Bundle(bundle)) => assert_eq!(bundle.receiver(), &vec![233]),
+            _ => unreachable!(),
+        }
+        match discord_rx.try_recv() {
+            Ok(Command::Bundle(bundle)) => {
+                assert_eq!(
+                    bundle.receiver(),
+                    cfg.fetch_repository_configure("BillyKing/Wrestling")
+                        .sink_send_to(SinkKind::Discord)
+                );
+                assert_eq!(bundle.notification().kind(), EventKind::PullRequest);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_route_gitea() {
+        let cfg = Config::new("example/sample.toml").unwrap();
+        let (bot_tx, mut bot_rx) = mpsc::channel(16);
+        let data = Arc::new(RwLock::new(ExtraData {
+            bot_tx,
+            sinks: Sinks::default(),
+            check_state: CheckState::default(),
+        }));
+        let sign = |body: &[u8]| {
+            let mut h = Hmac::<Sha256>::new_from_slice(b"2333").unwrap();
+            h.update(body);
+            format!("{:x}", h.finalize().into_bytes())
+        };
+        let send = |event: &str, signature: String, body: Vec<u8>| {
+            let request = HttpRequest::builder()
+                .header("X-Gitea-Event", event)
+                .header("X-Gitea-Signature", signature)
+                .body(Body::from(body))
+                .unwrap();
+            route_post(
+                request,
+                Extension(cfg.endpoints()[0].clone()),
+                Extension(cfg.clone()),
+                Extension(data.clone()),
+            )
+        };
+
+        let body = std::fs::read("example/gitea_push.json").unwrap();
+        let response = send("push", format!("sha256={}", sign(&body)), body.clone()).await;
+        assert_eq!(response.into_response().status(), 403);
+        let response = send("push", sign(&body), body).await;
+        assert_eq!(response.into_response().status(), 200);
+        match bot_rx.try_recv() {
+            Ok(Command::Bundle(bundle)) => assert!(bundle.text().starts_with(
+                "🔨 <a href=\"https://git.example.com/sample/test/commit/3e1d4f9ab7c2e8f05a6b1c9d2e3f4a5b6c7d8e9f\">1 new commit</a> <b>to sample/test:main</b>"
+            )),
+            _ => unreachable!(),
+        }
+
+        let body = std::fs::read("example/gitea_pull_request_comment.json").unwrap();
+        let response = send("pull_request_comment", sign(&body), body).await;
+        assert_eq!(response.into_response().status(), 200);
+        match bot_rx.try_recv() {
+            Ok(Command::Bundle(bundle)) => assert!(bundle.text().contains("pull request #3")),
+            _ => unreachable!(),
+        }
+
+        // Endpoint of Gitea does not accept GitHub requests
+        let request = HttpRequest::builder()
+            .header("X-GitHub-Event", "push")
+            .body(Body::from(std::fs::read("example/push.json").unwrap()))
+            .unwrap();
+        let response = route_post(
+            request,
+            Extension(cfg.endpoints()[0].clone()),
+            Extension(cfg.clone()),
+            Extension(data.clone()),
+        )
+        .await;
+        assert_eq!(response.into_response().status(), 500);
+    }
+
+    #[test]
+    fn test_parse_gitea() {
+        let s = std::fs::read_to_string("example/gitea_pull_request.json").unwrap();
+        let event: GitHubPullRequestEvent = serde_json::from_str(s.as_str()).unwrap();
+        assert_eq!(event.action_kind(), "opened");
+        assert_eq!(event.branch_name(), "main");
+        assert!(event.to_string().contains(
+            "<a href=\"https://git.example.com/sample/test/pulls/3\">pull request #3</a>"
+        ));
+    }
+
+    #[test]
+    fn test_parse_bitbucket() {
+        let s = std::fs::read("example/bitbucket_cloud_push.json").unwrap();
+        let events = match BitbucketEvent::parse("repo:push", &s).unwrap() {
+            Some(BitbucketEvent::Push(events)) => events,
+            _ => unreachable!(),
+        };
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind(), PushKind::Commits);
+        assert_eq!(events[0].branch_name(), "master");
+        let text = events[0].to_string();
+        assert!(
+            text.starts_with("🔨 <a href=\"https://bitbucket.org/sample/test/branches/compare/")
+        );
+        assert!(text.ends_with("\">f1e2d3c4</a>: Fix escaping of release notes"));
+        assert_eq!(events[1].kind(), PushKind::Tag);
+        assert!(events[1].created());
+
+        let s = std::fs::read("example/bitbucket_cloud_pullrequest.json").unwrap();
+        let event = match BitbucketEvent::parse("pullrequest:fulfilled", &s).unwrap() {
+            Some(BitbucketEvent::PullRequest(event)) => event,
+            _ => unreachable!(),
+        };
+        assert_eq!(event.action_kind(), "merged");
+        assert!(event.to_string().contains("<b>Add Bitbucket &lt;support&gt;</b>\nby <b>Kunoi Sayami</b>, <code>bitbucket</code> → <code>master</code>"));
+
+        let s = std::fs::read("example/bitbucket_server_refs_changed.json").unwrap();
+        let event = BitbucketEvent::parse("repo:refs_changed", &s)
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.get_full_name(), "BILLY/wrestling");
+        assert!(
+            matches!(event, BitbucketEvent::Push(ref events) if events[0].to_string()
+            == "🔨 <b>Kunoi Sayami</b> pushed <b>to BILLY/wrestling:master</b> (<code>ecddabb6..178864a7</code>)")
+        );
+
+        let s = std::fs::read("example/bitbucket_server_pr.json").unwrap();
+        let event = match BitbucketEvent::parse("pr:opened", &s).unwrap() {
+            Some(BitbucketEvent::PullRequest(event)) => event,
+            _ => unreachable!(),
+        };
+        assert_eq!(event.action_kind(), "opened");
+        assert!(event.to_string().contains("<a href=\"https://bitbucket.example.com/projects/BILLY/repos/wrestling/pull-requests/9\">pull request #9</a> <b>in BILLY/wrestling</b>"));
+
+        assert!(BitbucketEvent::parse("issue:created", b"{}")
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_route_bitbucket() {
+        let cfg = Config::new("example/sample.toml").unwrap();
+        let (bot_tx, mut bot_rx) = mpsc::channel(16);
+        let data = Arc::new(RwLock::new(ExtraData {
+            bot_tx,
+            sinks: Sinks::default(),
+            check_state: CheckState::default(),
+        }));
+        let body = std::fs::read("example/bitbucket_cloud_push.json").unwrap();
+        let mut h = Hmac::<Sha256>::new_from_slice(b"2333").unwrap();
+        h.update(&body);
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Event-Key", "repo:push".parse().unwrap());
+        headers.insert("X-Hub-Signature", "sha256=00".parse().unwrap());
+
+        let response = route_webhook(&BitbucketProvider, None, &headers, &body, &cfg, &data).await;
+        assert_eq!(response.status(), 403);
+
+        let signature = format!("sha256={:x}", h.finalize().into_bytes());
+        headers.insert("X-Hub-Signature", signature.parse().unwrap());
+        let response = route_webhook(&BitbucketProvider, None, &headers, &body, &cfg, &data).await;
+        assert_eq!(response.status(), 200);
+        // Commits and the new tag
+        assert!(matches!(bot_rx.try_recv(), Ok(Command::Bundle(_))));
+        assert!(matches!(bot_rx.try_recv(), Ok(Command::Bundle(_))));
+        assert!(bot_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_actions_notify_changed() {
+        let (bot_tx, mut bot_rx) = mpsc::channel(16);
+        let data = Arc::new(RwLock::new(ExtraData {
+            bot_tx,
+            sinks: Sinks::default(),
+            check_state: CheckState::default(),
+        }));
+        let settings = RepositoryBuilder::new()
+            .set_actions_notify(ActionsNotify::Changed)
+            .build();
+
+        let s = std::fs::read_to_string("example/workflow_run.json").unwrap();
+        let origin: serde_json::Value = serde_json::from_str(s.as_str()).unwrap();
+        let build = |conclusion: &str| {
+            let mut value = origin.clone();
+            value["workflow_run"]["conclusion"] = serde_json::Value::from(conclusion);
+            serde_json::from_value::<GitHubWorkflowRunEvent>(value).unwrap()
+        };
+
+        let mut sent = Vec::new();
+        for conclusion in [
+            "success", "failure", "failure", "skipped", "success", "success",
+        ] {
+            forward_check_event(&data, &settings, &build(conclusion)).await;
+            sent.push(matches!(bot_rx.try_recv(), Ok(Command::Bundle(_))));
+        }
+        assert_eq!(sent, vec![false, true, false, false, true, false]);
+        assert!(data.read().await.check_state.keys.is_empty());
+
+        // Ignored branches never touch the remembered state
+        let event = build("failure");
+        let settings = RepositoryBuilder::new()
+            .set_actions_notify(ActionsNotify::Changed)
+            .set_branch_ignore(vec![event.branch_name()])
+            .build();
+        forward_check_event(&data, &settings, &event).await;
+        assert!(bot_rx.try_recv().is_err());
+        assert!(data.read().await.check_state.keys.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_send_to_exited_sink() {
+        let (tx, rx) = mpsc::channel(16);
+        drop(rx);
+        let mut sinks = Sinks::default();
+        sinks.insert(SinkKind::Discord, tx);
+        let settings = RepositoryBuilder::new()
+            .set_sink_send_to(HashMap::from([(
+                SinkKind::Discord,
+                vec!["url".to_string()],
+            )]))
+            .build();
+        let s = std::fs::read_to_string("example/push.json").unwrap();
+        let event: GitHubPushEvent = serde_json::from_str(&s).unwrap();
+        let notification = Arc::new(Notification::new(&event));
+        sinks.send(&settings, &notification);
+    }
+
+    /// Local HTTP server which answers `response` and reports every request.
+    async fn mock_http_server(
+        response: &'static str,
+    ) -> (String, mpsc::Receiver<(String, HeaderMap, Vec<u8>)>) {
+        let (tx, rx) = mpsc::channel(16);
+        let router = axum::Router::new().fallback(axum::routing::any(
+            move |Extension(tx): Extension<mpsc::Sender<(String, HeaderMap, Vec<u8>)>>,
+                  mut request: HttpRequest<Body>| async move {
+                let mut body = Vec::new();
+                while let Some(Ok(chunk)) = request.body_mut().data().await {
+                    body.extend(chunk);
+                }
+                let path = request.uri().to_string();
+                tx.send((path, request.headers().clone(), body))
+                    .await
+                    .unwrap();
+                response
+            },
+        ));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(router.layer(Extension(tx)).into_make_service()),
+        );
+        (address, rx)
+    }
+
+    #[tokio::test]
+    async fn test_post_json_retry_after() {
+        let router = axum::Router::new().fallback(axum::routing::any(
+            |request: HttpRequest<Body>| async move {
+                let retry_after = request.uri().path().trim_start_matches('/').to_string();
+                (
+                    axum::http::StatusCode::TOO_MANY_REQUESTS,
+                    [("Retry-After", retry_after)],
+                )
+            },
+        ));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(router.into_make_service()),
+        );
+
+        let client = reqwest::Client::new();
+        let payload = serde_json::json!({});
+        let urls = ["NaN", "-1", "inf"].map(|x| format!("{}/{}", address, x));
+        let (nan, negative, infinite) = tokio::join!(
+            post_json(&client, &urls[0], &payload),
+            post_json(&client, &urls[1], &payload),
+            post_json(&client, &urls[2], &payload),
+        );
+        for result in [nan, negative, infinite] {
+            assert_eq!(result.err().unwrap().to_string(), "Rate limited");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_discord() {
+        let (address, mut requests) = mock_http_server("{}").await;
+        let (tx, rx) = mpsc::channel(16);
+        let sender = tokio::spawn(process_discord_message(rx));
+
+        let s = std::fs::read_to_string("example/push.json").unwrap();
+        let event: GitHubPushEvent = serde_json::from_str(&s).unwrap();
+        let compare = event.compare().to_string();
+        let url = format!("{}/api/webhooks/1/token", address);
+        tx.send(Command::Bundle(CommandBundle::new(
+            vec![url.clone()],
+            Arc::new(Notification::new(&event)),
+        )))
+        .await
+        .unwrap();
+        let s = std::fs::read_to_string("example/issue_comment.json").unwrap();
+        let event: GitHubIssueCommentEvent = serde_json::from_str(&s).unwrap();
+        tx.send(Command::Bundle(CommandBundle::new(
+            vec![url],
+            Arc::new(Notification::new(&event)),
+        )))
+        .await
+        .unwrap();
+        tx.send(Command::Terminate).await.unwrap();
+        sender.await.unwrap().unwrap();
+
+        let (path, headers, body) = requests.recv().await.unwrap();
+        assert_eq!(path, "/api/webhooks/1/token");
+        assert_eq!(headers["content-type"], "application/json");
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let embed = &payload["embeds"][0];
+        assert_eq!(
+            embed["title"],
+            "🔨 5 new commits to MagomeYae/test-action:master:"
+        );
+        assert_eq!(embed["url"], compare);
+        assert_eq!(embed["color"], 0x3498db);
+        assert_eq!(embed["author"]["name"], "MagomeYae");
+        assert_eq!(embed["fields"].as_array().unwrap().len(), 5);
+        assert!(embed.get("description").is_none());
+
+        let (_, _, body) = requests.recv().await.unwrap();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let embed = &payload["embeds"][0];
+        assert!(embed.get("fields").is_none());
+        assert!(embed["description"].as_str().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_slack() {
+        let (address, mut requests) = mock_http_server("{}").await;
+        let (tx, rx) = mpsc::channel(16);
+        let sender = tokio::spawn(process_slack_message(rx));
+
+        let s = std::fs::read_to_string("example/pull_request.json").unwrap();
+        let event: GitHubPullRequestEvent = serde_json::from_str(&s).unwrap();
+        tx.send(Command::Bundle(CommandBundle::new(
+            vec![format!("{}/services/T0/B0/token", address)],
+            Arc::new(Notification::new(&event)),
+        )))
+        .await
+        .unwrap();
+        tx.send(Command::Terminate).await.unwrap();
+        sender.await.unwrap().unwrap();
+
+        let (path, _, body) = requests.recv().await.unwrap();
+        assert_eq!(path, "/services/T0/B0/token");
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let text = payload["text"].as_str().unwrap();
+        assert!(text.starts_with(&format!(
+            "🟣 *{}* merged <{}|pull request #",
+            event.sender(),
+            event.pull_request().html_url()
+        )));
+        assert_eq!(payload["blocks"][0]["text"]["text"], text);
+        assert_eq!(
+            payload["blocks"][1]["elements"][0]["text"],
+            format!("{}:{}", event.get_full_name(), event.branch_name())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_slack_hung_webhook() {
+        // Accepts connections but never responds
+        let hung = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let hung_address = format!("http://{}", hung.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut streams = Vec::new();
+            while let Ok((stream, _)) = hung.accept().await {
+                streams.push(stream);
+            }
+        });
+        let (address, mut requests) = mock_http_server("ok").await;
+        let (tx, rx) = mpsc::channel(16);
+        let sender = tokio::spawn(process_json_webhook("slack", rx, slack_payload, 1));
+
+        let s = std::fs::read_to_string("example/pull_request.json").unwrap();
+        let event: GitHubPullRequestEvent = serde_json::from_str(&s).unwrap();
+        let notification = Arc::new(Notification::new(&event));
+        for url in [hung_address, address] {
+            tx.send(Command::Bundle(CommandBundle::new(
+                vec![format!("{}/hooks/token", url)],
+                notification.clone(),
+            )))
+            .await
+            .unwrap();
+        }
+        let request = tokio::time::timeout(std::time::Duration::from_secs(5), requests.recv())
+            .await
+            .expect("hung webhook holds up the following messages");
+        assert_eq!(request.unwrap().0, "/hooks/token");
+        tx.send(Command::Terminate).await.unwrap();
+        sender.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_matrix() {
+        let (address, mut requests) =
+            mock_http_server(r#"{"room_id": "!abc:localhost", "event_id": "$1"}"#).await;
+        let matrix: TomlMatrix = toml::from_str(&format!(
+            "homeserver = \"{}/\"\naccess_token = \"syt_token\"",
+            address
+        ))
+        .unwrap();
+        let (tx, rx) = mpsc::channel(16);
+        let sender = tokio::spawn(process_matrix_message(
+            Matrix::try_from(&matrix).unwrap(),
+            rx,
+        ));
+
+        let s = std::fs::read_to_string("example/push.json").unwrap();
+        let event: GitHubPushEvent = serde_json::from_str(&s).unwrap();
+        let notification = Arc::new(Notification::new(&event));
+        for receiver in [
+            vec!["#room:localhost".to_string()],
+            vec!["#room:localhost".to_string()],
+        ] {
+            tx.send(Command::Bundle(CommandBundle::new(
+                receiver,
+                notification.clone(),
+            )))
+            .await
+            .unwrap();
+        }
+        tx.send(Command::Terminate).await.unwrap();
+        sender.await.unwrap().unwrap();
+
+        let (path, headers, _) = requests.recv().await.unwrap();
+        assert_eq!(path, "/_matrix/client/v3/join/%23room:localhost");
+        assert_eq!(headers["authorization"], "Bearer syt_token");
+        let (path, _, body) = requests.recv().await.unwrap();
+        assert!(path.starts_with("/_matrix/client/v3/rooms/!abc:localhost/send/m.room.message/"));
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["msgtype"], "m.notice");
+        assert_eq!(payload["format"], "org.matrix.custom.html");
+        let formatted_body = payload["formatted_body"].as_str().unwrap();
+        assert!(formatted_body.starts_with("🔨 <a href="));
+        assert!(formatted_body.contains("<br>"));
+        assert!(!payload["body"].as_str().unwrap().contains('<'));
+        // Joined room is remembered, transaction ID changes
+        let (second, _, _) = requests.recv().await.unwrap();
+        assert!(second.contains("/send/m.room.message/"));
+        assert_ne!(second, path);
+    }
+
+    #[tokio::test]
+    async fn test_teams() {
+        let (address, mut requests) = mock_http_server("1").await;
+        let (tx, rx) = mpsc::channel(16);
+        let sender = tokio::spawn(process_teams_message(Teams::default(), rx));
+
+        let s = std::fs::read_to_string("example/push.json").unwrap();
+        let event: GitHubPushEvent = serde_json::from_str(&s).unwrap();
+        tx.send(Command::Bundle(CommandBundle::new(
+            vec![format!("{}/webhookb2/1", address)],
+            Arc::new(Notification::new(&event)),
+        )))
+        .await
+        .unwrap();
+        tx.send(Command::Terminate).await.unwrap();
+        sender.await.unwrap().unwrap();
+
+        let (_, _, body) = requests.recv().await.unwrap();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let card = &payload["attachments"][0]["content"];
+        assert_eq!(card["type"], "AdaptiveCard");
+        let facts = card["body"].as_array().unwrap().last().unwrap()["facts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| (x["title"].as_str().unwrap(), x["value"].as_str().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            facts,
+            vec![
+                ("Repository", "MagomeYae/test-action"),
+                ("Branch", "master"),
+                ("Author", "MagomeYae"),
+            ]
+        );
+        assert_eq!(card["actions"][0]["title"], "View compare");
+        assert_eq!(card["actions"][0]["url"], event.compare());
+    }
+
+    #[tokio::test]
+    async fn test_webhook() {
+        let (address, mut requests) = mock_http_server("").await;
+        let webhook: TomlWebhook = toml::from_str(&format!(
+            "name = \"ci\"\nurl = \"{}/hooks/ci\"\nsecrets = \"114514\"\nsignature_header = \"X-Signature\"\ntimeout = 5\n[headers]\nAuthorization = \"Bearer 1919810\"",
+            address
+        ))
+        .unwrap();
+        let webhooks = HashMap::from([(
+            webhook.name().to_string(),
+            Webhook::try_from(&webhook).unwrap(),
+        )]);
+        let (tx, rx) = mpsc::channel(16);
+        let sender = tokio::spawn(process_webhook_message(webhooks, rx));
+
+        let s = std::fs::read_to_string("example/push.json").unwrap();
+        let event: GitHubPushEvent = serde_json::from_str(&s).unwrap();
+        tx.send(Command::Bundle(CommandBundle::new(
+            vec!["undefined".to_string(), "ci".to_string()],
+            Arc::new(Notification::new(&event)),
+        )))
+        .await
+        .unwrap();
+        tx.send(Command::Terminate).await.unwrap();
+        sender.await.unwrap().unwrap();
+
+        let (path, headers, body) = requests.recv().await.unwrap();
+        assert_eq!(path, "/hooks/ci");
+        assert!(requests.try_recv().is_err());
+        assert_eq!(headers["Authorization"], "Bearer 1919810");
+        let mut h = Hmac::<Sha256>::new_from_slice(b"114514").unwrap();
+        h.update(&body);
+        assert_eq!(
+            headers["X-Signature"],
+            format!("sha256={:x}", h.finalize().into_bytes())
+        );
+
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["version"], 1);
+        assert_eq!(payload["kind"], "push");
+        assert_eq!(payload["repository"], "MagomeYae/test-action");
+        assert_eq!(payload["branch"], "master");
+        assert_eq!(payload["actor"], "MagomeYae");
+        assert_eq!(payload["url"], event.compare());
+        assert!(!payload["text"].as_str().unwrap().contains("<a"));
+        let commits = payload["commits"].as_array().unwrap();
+        assert_eq!(commits.len(), event.commits().len());
+        assert_eq!(commits[0]["id"], event.commits()[0].id().as_str());
+
+        let s = std::fs::read_to_string("example/push_tag.json").unwrap();
+        let event: GitHubPushEvent = serde_json::from_str(&s).unwrap();
+        let notification = Notification::new(&event);
+        assert_eq!(notification.kind(), EventKind::Tag);
+        let payload = serde_json::to_value(WebhookPayload::from(&notification)).unwrap();
+        assert_eq!(payload["kind"], "tag");
+        assert!(payload["branch"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_ntfy() {
+        let (address, mut requests) = mock_http_server("{}").await;
+        let ntfy: TomlNtfy =
+            toml::from_str(&format!("server = \"{}/\"\ntoken = \"tk_114514\"", address)).unwrap();
+        let (tx, rx) = mpsc::channel(16);
+        let sender = tokio::spawn(process_ntfy_message(Ntfy::from(&ntfy), rx));
+
+        let s = std::fs::read_to_string("example/push.json").unwrap();
+        let event: GitHubPushEvent = serde_json::from_str(&s).unwrap();
+        tx.send(Command::Bundle(CommandBundle::new(
+            vec!["wrestling".to_string(), format!("{}/other", address)],
+            Arc::new(Notification::new(&event)),
+        )))
+        .await
+        .unwrap();
+        tx.send(Command::Terminate).await.unwrap();
+        sender.await.unwrap().unwrap();
+
+        let (path, headers, body) = requests.recv().await.unwrap();
+        assert_eq!(path, "/");
+        assert_eq!(headers["Authorization"], "Bearer tk_114514");
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["topic"], "wrestling");
+        assert_eq!(payload["priority"], 3);
+        assert_eq!(payload["click"], event.compare());
+        assert!(!payload["message"].as_str().unwrap().contains("<a"));
+
+        // Token is not sent to topics of other servers
+        let (path, headers, body) = requests.recv().await.unwrap();
+        assert_eq!(path, "/");
+        assert!(!headers.contains_key("Authorization"));
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["topic"], "other");
+    }
+
+    #[tokio::test]
+    async fn test_gotify() {
+        let (address, mut requests) = mock_http_server("{}").await;
+        let gotify: TomlGotify = toml::from_str(&format!("server = \"{}\"", address)).unwrap();
+        let (tx, rx) = mpsc::channel(16);
+        let sender = tokio::spawn(process_gotify_message(Gotify::from(&gotify), rx));
+
+        let s = std::fs::read_to_string("example/workflow_run.json").unwrap();
+        let event: GitHubWorkflowRunEvent = serde_json::from_str(&s).unwrap();
+        let s = std::fs::read_to_string("example/star.json").unwrap();
+        let star: GitHubStarEvent = serde_json::from_str(&s).unwrap();
+        for notification in [Notification::new(&event), Notification::new(&star)] {
+            tx.send(Command::Bundle(CommandBundle::new(
+                vec!["A1145141919".to_string()],
+                Arc::new(notification),
+            )))
+            .await
+            .unwrap();
+        }
+        tx.send(Command::Terminate).await.unwrap();
+        sender.await.unwrap().unwrap();
+
+        let (path, headers, body) = requests.recv().await.unwrap();
+        assert_eq!(path, "/message");
+        assert_eq!(headers["X-Gotify-Key"], "A1145141919");
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["priority"], 8);
+        assert_eq!(
+            payload["extras"]["client::notification"]["click"]["url"],
+            "https://github.com/MagomeYae/test-action/actions/runs/3326571836"
+        );
+
+        let (_, _, body) = requests.recv().await.unwrap();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["priority"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_irc() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let irc: TomlIrc = toml::from_str(&format!(
+            "server = \"127.0.0.1\"\nport = {}\ntls = false\nnickname = \"bot\"\nflood_interval = 0",
+            listener.local_addr().unwrap().port()
+        ))
+        .unwrap();
+        let (tx, rx) = mpsc::channel(16);
+        let sender = tokio::spawn(process_irc_message(
+            Irc::from(&irc),
+            vec!["#wrestling".to_string()],
+            rx,
+        ));
+
+        // Nickname is taken, then the connection is lost after joining
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "NICK bot");
+        assert!(lines
+            .next_line()
+            .await
+            .unwrap()
+            .unwrap()
+            .starts_with("USER bot 0 * :"));
+        writer
+            .write_all(b":mock 433 * bot :Nickname is already in use\r\n")
+            .await
+            .unwrap();
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "NICK bot_");
+        writer
+            .write_all(b":mock 001 bot_ :Welcome\r\n")
+            .await
+            .unwrap();
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "JOIN #wrestling");
+        drop((lines, writer));
+
+        let s = std::fs::read_to_string("example/push.json").unwrap();
+        let event: GitHubPushEvent = serde_json::from_str(&s).unwrap();
+        tx.send(Command::Bundle(CommandBundle::new(
+            vec!["#wrestling".to_string(), "#other".to_string()],
+            Arc::new(Notification::new(&event)),
+        )))
+        .await
+        .unwrap();
+
+        // Message received while reconnecting is sent after joining again, joining #other
+        // fails at first and is retried for the next message
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "NICK bot");
+        lines.next_line().await.unwrap().unwrap();
+        writer
+            .write_all(b":mock 001 bot :Welcome\r\nPING :114514\r\n")
+            .await
+            .unwrap();
+        let count = build_lines(&Notification::new(&event)).len();
+        let mut received = Vec::new();
+        let mut joins = 0;
+        loop {
+            let line = lines.next_line().await.unwrap().unwrap();
+            if line == "JOIN #wrestling" {
+                writer
+                    .write_all(b":bot!bot@mock JOIN #Wrestling\r\n")
+                    .await
+                    .unwrap();
+            }
+            if line == "JOIN #other" {
+                joins += 1;
+                if joins == 1 {
+                    writer
+                        .write_all(b":mock 474 bot #other :Cannot join channel (+b)\r\n")
+                        .await
+                        .unwrap();
+                    tx.send(Command::Bundle(CommandBundle::new(
+                        vec!["#other".to_string()],
+                        Arc::new(Notification::new(&event)),
+                    )))
+                    .await
+                    .unwrap();
+                } else {
+                    writer
+                        .write_all(b":bot!bot@mock JOIN :#other\r\n")
+                        .await
+                        .unwrap();
+                }
+            }
+            if line.starts_with("QUIT") {
+                break;
+            }
+            received.push(line);
+            if received
+                .iter()
+                .filter(|x| x.starts_with("PRIVMSG #other :"))
+                .count()
+                == count
+            {
+                tx.send(Command::Terminate).await.unwrap();
+            }
+        }
+        sender.await.unwrap().unwrap();
+
+        assert!(received.contains(&"PONG :114514".to_string()));
+        assert_eq!(joins, 2);
+        let messages = received
+            .iter()
+            .filter_map(|x| x.strip_prefix("PRIVMSG #wrestling :"))
+            .collect::<Vec<&str>>();
+        assert_eq!(messages.len(), count);
+        assert!(messages[0].contains(" \x02to MagomeYae/test-action"));
+        assert!(messages[0].ends_with(&format!(" {}", event.compare())));
+        assert!(messages.iter().all(|x| !x.contains("<a")));
+        let first_other = received
+            .iter()
+            .position(|x| x.starts_with("PRIVMSG #other :"))
+            .unwrap();
+        assert!(first_other > received.iter().rposition(|x| x == "JOIN #other").unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_irc_flood() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let irc: TomlIrc = toml::from_str(&format!(
+            "server = \"127.0.0.1\"\nport = {}\ntls = false\nnickname = \"bot\"\nflood_interval = 100\nflood_burst = 1",
+            listener.local_addr().unwrap().port()
+        ))
+        .unwrap();
+        let (tx, rx) = mpsc::channel(16);
+        let sender = tokio::spawn(process_irc_message(Irc::from(&irc), vec![], rx));
+        let s = std::fs::read_to_string("example/push.json").unwrap();
+        let event: GitHubPushEvent = serde_json::from_str(&s).unwrap();
+        let expected = build_lines(&Notification::new(&event));
+        assert!(expected.len() > 2);
+        tx.send(Command::Bundle(CommandBundle::new(
+            vec!["#wrestling".to_string()],
+            Arc::new(Notification::new(&event)),
+        )))
+        .await
+        .unwrap();
+
+        // Connection is lost after the first line
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        lines.next_line().await.unwrap().unwrap();
+        lines.next_line().await.unwrap().unwrap();
+        writer
+            .write_all(b":mock 001 bot :Welcome\r\n")
+            .await
+            .unwrap();
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "JOIN #wrestling");
+        writer
+            .write_all(b":bot!bot@mock JOIN #wrestling\r\n")
+            .await
+            .unwrap();
+        assert_eq!(
+            lines.next_line().await.unwrap().unwrap(),
+            format!("PRIVMSG #wrestling :{}", expected[0])
+        );
+        drop((lines, writer));
+
+        // The rest is sent after reconnecting, PING is answered while waiting for flood control
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        lines.next_line().await.unwrap().unwrap();
+        lines.next_line().await.unwrap().unwrap();
+        writer
+            .write_all(b":mock 001 bot :Welcome\r\n")
+            .await
+            .unwrap();
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "JOIN #wrestling");
+        writer
+            .write_all(b":bot!bot@mock JOIN #wrestling\r\n")
+            .await
+            .unwrap();
+        assert_eq!(
+            lines.next_line().await.unwrap().unwrap(),
+            format!("PRIVMSG #wrestling :{}", expected[1])
+        );
+        writer.write_all(b"PING :1919810\r\n").await.unwrap();
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "PONG :1919810");
+
+        let mut received = vec![];
+        for _ in 2..expected.len() {
+            received.push(lines.next_line().await.unwrap().unwrap());
+        }
+
+        // Not in channel any more, so it is joined again before next message
+        writer
+            .write_all(b":mock 404 bot #wrestling :Cannot send to channel\r\nPING :0\r\n")
+            .await
+            .unwrap();
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "PONG :0");
+        tx.send(Command::Bundle(CommandBundle::new(
+            vec!["#wrestling".to_string()],
+            Arc::new(Notification::new(&event)),
+        )))
+        .await
+        .unwrap();
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "JOIN #wrestling");
+        tx.send(Command::Terminate).await.unwrap();
+        sender.await.unwrap().unwrap();
+
+        assert_eq!(
+            received,
+            expected[2..]
+                .iter()
+                .map(|x| format!("PRIVMSG #wrestling :{}", x))
+                .collect::<Vec<String>>()
+        );
+    }
+
+    #[test]
+    fn test_irc_lines() {
+        let s = std::fs::read_to_string("example/push.json").unwrap();
+        let mut value: serde_json::Value = serde_json::from_str(&s).unwrap();
+        let commit = value["commits"][0].clone();
+        let mut commits = vec![];
+        for i in 0..20 {
+            let mut commit = commit.clone();
+            commit["message"] =
+                serde_json::Value::from(format!("{} {}\r\nQUIT", i, "a".repeat(500)));
+            commits.push(commit);
+        }
+        value["commits"] = serde_json::Value::from(commits);
+        value["compare"] = serde_json::Value::from("https://example.com/\r\nQUIT :bye\0");
+        let event: GitHubPushEvent = serde_json::from_value(value).unwrap();
+        let lines = build_lines(&Notification::new(&event));
+        assert_eq!(lines.len(), 10);
+        assert!(lines
+            .iter()
+            .all(|x| x.len() <= 400 && !x.contains(['\r', '\n', '\0'])));
+        assert!(lines.last().unwrap().starts_with("... and "));
+
+        let mut pending = VecDeque::new();
+        for i in 0..300 {
+            let notification = Arc::new(Notification::new(&event));
+            let dropped = push_pending(
+                &mut pending,
+                CommandBundle::new(vec![i.to_string()], notification),
+            );
+            assert_eq!(dropped.is_some(), i >= 256);
+        }
+        assert_eq!(pending.len(), 256);
+        assert_eq!(pending[0].receiver(), &vec!["44".to_string()]);
+    }
+
+    /// Read from client until `pattern` is received, return what is read.
+    async fn read_until(stream: &mut tokio::net::TcpStream, pattern: &str) -> String {
+        let mut received = Vec::new();
+        let mut buf = [0u8; 4096];
+        while !String::from_utf8_lossy(&received).contains(pattern) {
+            let n = stream.read(&mut buf).await.unwrap();
+            assert_ne!(n, 0, "Closed before {:?}", pattern);
+            received.extend_from_slice(&buf[..n]);
+        }
+        String::from_utf8(received).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_xmpp() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let xmpp: TomlXmpp = toml::from_str(&format!(
+            "jid = \"bot@example.com\"\npassword = \"114514\"\nserver = \"127.0.0.1\"\nport = {}\nstarttls = false\nallow_insecure_auth = true",
+            listener.local_addr().unwrap().port()
+        ))
+        .unwrap();
+        let (tx, rx) = mpsc::channel(16);
+        let sender = tokio::spawn(process_xmpp_message(
+            Xmpp::try_from(&xmpp).unwrap(),
+            vec!["xmpp:wrestling@conference.example.com?join".to_string()],
+            rx,
+        ));
+
+        let header = "<?xml version='1.0'?><stream:stream xmlns='jabber:client' xmlns:stream='http://etherx.jabber.org/streams' id='1' from='example.com' version='1.0'>";
+        let (mut stream, _) = listener.accept().await.unwrap();
+        assert!(read_until(&mut stream, "version=\"1.0\"")
+            .await
+            .contains("to=\"example.com\""));
+        stream
+            .write_all(format!("{}<stream:features><mechanisms xmlns='urn:ietf:params:xml:ns:xmpp-sasl'><mechanism>SCRAM-SHA-1</mechanism><mechanism>PLAIN</mechanism></mechanisms></stream:features>", header).as_bytes())
+            .await
+            .unwrap();
+        // base64 of "\0bot\0114514"
+        assert!(read_until(&mut stream, "</auth>")
+            .await
+            .contains(">AGJvdAAxMTQ1MTQ=</auth>"));
+        stream
+            .write_all(b"<success xmlns='urn:ietf:params:xml:ns:xmpp-sasl'/>")
+            .await
+            .unwrap();
+        read_until(&mut stream, "<stream:stream").await;
+        stream
+            .write_all(format!("{}<stream:features><bind xmlns='urn:ietf:params:xml:ns:xmpp-bind'/></stream:features>", header).as_bytes())
+            .await
+            .unwrap();
+        read_until(&mut stream, "</iq>").await;
+        stream
+            .write_all(b"<iq type='result' id='bind'><bind xmlns='urn:ietf:params:xml:ns:xmpp-bind'><jid>bot@example.com/webhook</jid></bind></iq>")
+            .await
+            .unwrap();
+        let joined = read_until(&mut stream, "</presence>").await;
+        assert!(joined.starts_with("<presence/>"));
+        assert!(joined.contains("<presence to=\"wrestling@conference.example.com/bot\">"));
+
+        stream
+            .write_all(
+                b"<iq type='get' id='ping1' from='example.com'><ping xmlns='urn:xmpp:ping'/></iq>",
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            read_until(&mut stream, "/>").await,
+            "<iq type=\"result\" id=\"ping1\" to=\"example.com\"/>"
+        );
+
+        let s = std::fs::read_to_string("example/push.json").unwrap();
+        let event: GitHubPushEvent = serde_json::from_str(&s).unwrap();
+        tx.send(Command::Bundle(CommandBundle::new(
+            vec![
+                "billy@example.com".to_string(),
+                "xmpp:wrestling@conference.example.com?join".to_string(),
+            ],
+            Arc::new(Notification::new(&event)),
+        )))
+        .await
+        .unwrap();
+        tx.send(Command::Terminate).await.unwrap();
+        let received = read_until(&mut stream, "</stream:stream>").await;
+        sender.await.unwrap().unwrap();
+
+        let messages = received.split("</message>").collect::<Vec<&str>>();
+        assert!(messages[0].starts_with("<message to=\"billy@example.com\" type=\"chat\""));
+        assert!(messages[1]
+            .starts_with("<message to=\"wrestling@conference.example.com\" type=\"groupchat\""));
+        let body = messages[0]
+            .split_once("<body>")
+            .unwrap()
+            .1
+            .split_once("</body>")
+            .unwrap()
+            .0;
+        assert!(body.ends_with(&format!("\n{}", event.compare())));
+        assert!(!body.contains("&lt;a"));
+        assert!(messages[0].contains(&format!(
+            "<html xmlns=\"http://jabber.org/protocol/xhtml-im\"><body xmlns=\"http://www.w3.org/1999/xhtml\"><p>🔨 <a href=\"{}\">",
+            event.compare()
+        )));
+        assert!(messages[0].contains("<strong>to MagomeYae/test-action:master</strong>:<br/>"));
+    }
+
+    /// Local SMTP server which accepts everything and reports the data of each mail.
+    async fn mock_smtp_server() -> (u16, mpsc::Receiver<String>) {
+        let (tx, rx) = mpsc::channel(16);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut lines = BufReader::new(reader).lines();
+                    writer.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let reply: &[u8] = match line.split(' ').next().unwrap() {
+                            "DATA" => {
+                                writer.write_all(b"354 Go ahead\r\n").await.unwrap();
+                                let mut data = Vec::new();
+                                while let Ok(Some(line)) = lines.next_line().await {
+                                    if line == "." {
+                                        break;
+                                    }
+                                    data.push(line);
+                                }
+                                tx.send(data.join("\n")).await.unwrap();
+                                b"250 OK\r\n"
+                            }
+                            "QUIT" => b"221 Bye\r\n",
+                            _ => b"250 OK\r\n",
+                        };
+                        writer.write_all(reply).await.unwrap();
+                    }
+                });
+            }
+        });
+        (port, rx)
+    }
+
+    #[tokio::test]
+    async fn test_email() {
+        let (port, mut mails) = mock_smtp_server().await;
+        let s = std::fs::read_to_string("example/push.json").unwrap();
+        let push: GitHubPushEvent = serde_json::from_str(&s).unwrap();
+        let s = std::fs::read_to_string("example/issues.json").unwrap();
+        let issue: GitHubIssuesEvent = serde_json::from_str(&s).unwrap();
+        let build = |digest: &str| {
+            let smtp: TomlSmtp = toml::from_str(&format!(
+                "host = \"127.0.0.1\"\nport = {}\nsecurity = \"none\"\nfrom = \"Webhook <webhook@example.com>\"\n{}",
+                port, digest
+            ))
+            .unwrap();
+            Smtp::try_from(&smtp).unwrap()
+        };
+        let send_all = |smtp: Smtp| async {
+            let (tx, rx) = mpsc::channel(16);
+            let sender = tokio::spawn(process_email_message(smtp, rx));
+            for notification in [Notification::new(&push), Notification::new(&issue)] {
+                tx.send(Command::Bundle(CommandBundle::new(
+                    vec!["billy@example.com".to_string()],
+                    Arc::new(notification),
+                )))
+                .await
+                .unwrap();
+            }
+            tx.send(Command::Terminate).await.unwrap();
+            sender.await.unwrap().unwrap();
+        };
+
+        send_all(build("")).await;
+        let mail = mails.recv().await.unwrap();
+        assert!(mail.contains("From: Webhook <webhook@example.com>"));
+        assert!(mail.contains("To: billy@example.com"));
+        assert!(mail.contains("Content-Type: text/html"));
+        assert!(mail.contains("Content-Type: text/plain"));
+        assert!(mails.recv().await.is_some());
+
+        // Both events are sent in one mail when the daemon exits
+        send_all(build("digest_interval = 10")).await;
+        let mail = mails.recv().await.unwrap();
+        assert!(mail.contains("Subject: Digest of 2 events"));
+        assert!(mails.try_recv().is_err());
+
+        // Only the latest events are kept, the digest tells how many are dropped
+        let (tx, rx) = mpsc::channel(16);
+        let sender = tokio::spawn(process_email_message(build("digest_interval = 10"), rx));
+        let notification = Arc::new(Notification::new(&issue));
+        for _ in 0..300 {
+            tx.send(Command::Bundle(CommandBundle::new(
+                vec!["billy@example.com".to_string()],
+                notification.clone(),
+            )))
+            .await
+            .unwrap();
+        }
+        tx.send(Command::Terminate).await.unwrap();
+        sender.await.unwrap().unwrap();
+        let mail = mails.recv().await.unwrap();
+        assert!(mail.contains("Subject: Digest of 300 events"));
+        assert!(mail.contains("44 older events were dropped"));
+        assert!(mails.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_convert_html() {
+        let html = "🔨 <a href=\"https://example.com/?a=1&amp;b=2\">2 new commits</a> <b>to a_b:main</b>:\n\n<code>x*y</code> 1 &lt; 2 <script>";
+        assert_eq!(
+            html_to_plain(html),
+            "🔨 2 new commits to a_b:main:\n\nx*y 1 < 2 <script>"
+        );
+        assert_eq!(
+            convert_html(html, Markup::Discord),
+            "🔨 [2 new commits](https://example.com/?a=1&b=2) **to a\\_b:main**:\n\n`x*y` 1 < 2 <script>"
+        );
+        assert_eq!(
+            convert_html(html, Markup::Slack),
+            "🔨 <https://example.com/?a=1&b=2|2 new commits> *to a_b:main*:\n\n`x*y` 1 &lt; 2 &lt;script&gt;"
+        );
+        assert_eq!(
+            convert_html(html, Markup::Html),
+            "🔨 <a href=\"https://example.com/?a=1&amp;b=2\">2 new commits</a> <b>to a_b:main</b>:<br><br><code>x*y</code> 1 &lt; 2 &lt;script&gt;"
+        );
+        assert_eq!(
+            convert_html(html, Markup::Xhtml),
+            "🔨 <a href=\"https://example.com/?a=1&amp;b=2\">2 new commits</a> <strong>to a_b:main</strong>:<br/><br/><code>x*y</code> 1 &lt; 2 &lt;script&gt;"
+        );
+        assert_eq!(
+            convert_html(html, Markup::Irc),
+            "🔨 2 new commits \x02to a_b:main\x02:  x*y 1 < 2 <script>"
+        );
+    }
+
+    #[test]
+    fn test_markdown_to_telegram_html() {
+        assert_eq!(
+            markdown_to_telegram_html("# Title\n\n* **bold** and `a<b`\n* [link](https://example.com)"),
+            "<b>Title</b>\n\n• <b>bold</b> and <code>a&lt;b</code>\n• <a href=\"https://example.com\">link</a>"
+        );
+        assert_eq!(
+            markdown_to_telegram_html("1. ~~first~~\n2. _second_\n\n<br> tail"),
+            "1. <s>first</s>\n2. <i>second</i>\n\n&lt;br&gt; tail"
+        );
+    }
+
     #[test]
     fn test_basic_parse() {
         for entry in WalkDir::new("example") {